@@ -0,0 +1,376 @@
+use std::collections::BTreeMap;
+use crate::grading::{self, Hierarchy, StudentResult};
+use crate::models::{Config, Question};
+
+// Item analysis and reliability: how each question and the exam as a whole behaved.
+// The sample is the students that go into the statistics and have a total;
+// the total is the weighted percentage before the student's adjustments, so
+// late penalties and misconduct do not distort the item statistics.
+// Standard deviations are population values (divided by n).
+
+// Share of the sample in each of the upper and lower groups
+pub const GROUP_RATIO: f32 = 0.27;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStats {
+    pub question_id: u32,
+    pub name: String,
+    // nesting level of a part, for indentation
+    pub depth: usize,
+    pub full_score: u32,
+    // students with a score on the question
+    pub n: usize,
+    pub mean: Option<f32>,
+    pub sd: Option<f32>,
+    // mean / full_score (正答率)
+    pub facility: Option<f32>,
+    // (upper mean - lower mean) / full_score over the upper and lower 27%
+    pub discrimination: Option<f32>,
+    // correlation of the question score with the total
+    pub point_biserial: Option<f32>,
+    // (score, count) in ascending score order
+    pub distribution: Vec<(f32, usize)>,
+}
+
+impl ItemStats {
+
+    // The question rewards the weaker students more than the stronger ones
+    pub fn is_flagged(&self) -> bool {
+        self.discrimination.is_some_and(|d| d < 0.0)
+            || self.point_biserial.is_some_and(|r| r < 0.0)
+    }
+}
+
+// Statistics of every question in configured order
+pub fn item_analysis(config: &Config) -> Vec<ItemStats> {
+    let questions = &config.questions;
+    let tree = Hierarchy::new(questions);
+    let sample: Vec<_> = grading::evaluate(config).into_iter()
+        .filter(|r| r.in_stats)
+        .filter_map(|r| Some((r.raw_percent?, r)))
+        .collect();
+
+    questions.iter().enumerate().map(|(i, q)| {
+        let full = tree.full_score(questions, i);
+        // (question score, total); dropped parts are not part of the exam for that student
+        let mut pairs: Vec<(f32, f32)> = sample.iter()
+            .filter(|(_, r)| !r.dropped[i])
+            .filter_map(|(total, r)| Some((item_score(&tree, questions, r, i)?, *total)))
+            .collect();
+        let items: Vec<f32> = pairs.iter().map(|p| p.0).collect();
+        let totals: Vec<f32> = pairs.iter().map(|p| p.1).collect();
+
+        let item_mean = mean(&items);
+        let full_f = (full > 0).then_some(full as f32);
+
+        // lowest totals first; the same number of students on each side
+        pairs.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let k = ((pairs.len() as f32 * GROUP_RATIO).round() as usize).max(1);
+        let discrimination = (pairs.len() >= 2 * k).then(|| {
+            let lower: Vec<f32> = pairs[..k].iter().map(|p| p.0).collect();
+            let upper: Vec<f32> = pairs[pairs.len() - k..].iter().map(|p| p.0).collect();
+            Some((mean(&upper)? - mean(&lower)?) / full_f?)
+        }).flatten();
+
+        let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+        for v in items.iter() {
+            *counts.entry((v * 10000.0).round() as i64).or_default() += 1;
+        }
+
+        ItemStats {
+            question_id: q.id,
+            name: q.name.clone(),
+            depth: tree.depth(i),
+            full_score: full,
+            n: items.len(),
+            mean: item_mean,
+            sd: sd(&items),
+            facility: item_mean.zip(full_f).map(|(m, f)| m / f),
+            discrimination,
+            point_biserial: correlation(&items, &totals),
+            distribution: counts.into_iter().map(|(v, c)| (v as f32 / 10000.0, c)).collect(),
+        }
+    }).collect()
+}
+
+// Score of a question as the engine counts it: a blank top-level bonus question is 0
+fn item_score(tree: &Hierarchy, questions: &[Question], r: &StudentResult, i: usize) -> Option<f32> {
+    let blank_bonus = questions[i].bonus && tree.parent(i).is_none() && r.statuses[i].is_none();
+    r.scores[i].or(blank_bonus.then_some(0.0))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reliability {
+    // students with a score on every item
+    pub n: usize,
+    // completed students left out because an item was excluded for them (e.g. 免除);
+    // alpha needs every item of every student, so they cannot take part
+    pub excluded: usize,
+    // top-level questions except bonus ones; a group counts as one item with the sum of its parts
+    pub items: Vec<u32>,
+    pub alpha: Option<f32>,
+    // standard error of measurement, in points of the item sum
+    pub sem: Option<f32>,
+    // full score of the item sum, to read sem against
+    pub full_score: u32,
+    // alpha of the remaining items when the item is left out, same order as items
+    pub alpha_if_deleted: Vec<Option<f32>>,
+}
+
+// Cronbach's alpha over the top-level questions of the completed students.
+// Bonus questions are not items: they are optional and outside the full score.
+pub fn reliability(config: &Config) -> Reliability {
+    let questions = &config.questions;
+    let tree = Hierarchy::new(questions);
+    let roots: Vec<usize> = tree.roots().filter(|&i| !questions[i].bonus).collect();
+
+    // rows of the completed matrix: one score per item. A completed student can only
+    // miss an item through an Exclude status; such rows are counted, not imputed.
+    let rows: Vec<Option<Vec<f32>>> = grading::evaluate(config).into_iter()
+        .filter(|r| r.in_stats && r.is_complete())
+        .map(|r| roots.iter().map(|&i| r.scores[i]).collect())
+        .collect();
+    let excluded = rows.iter().filter(|row| row.is_none()).count();
+    let matrix: Vec<Vec<f32>> = rows.into_iter().flatten().collect();
+    let column = |j: usize| -> Vec<f32> { matrix.iter().map(|row| row[j]).collect() };
+
+    // alpha of the listed columns
+    let alpha_of = |cols: &[usize]| -> Option<f32> {
+        let k = cols.len();
+        if k < 2 || matrix.len() < 2 {
+            return None;
+        }
+        let item_var: f32 = cols.iter().map(|&j| sd(&column(j)).unwrap_or(0.0).powi(2)).sum();
+        let totals: Vec<f32> = matrix.iter().map(|row| cols.iter().map(|&j| row[j]).sum()).collect();
+        let total_var = sd(&totals)?.powi(2);
+        (total_var > 0.0).then(|| k as f32 / (k - 1) as f32 * (1.0 - item_var / total_var))
+    };
+
+    let all: Vec<usize> = (0..roots.len()).collect();
+    let alpha = alpha_of(&all);
+    let totals: Vec<f32> = matrix.iter().map(|row| row.iter().sum()).collect();
+    let sem = alpha.zip(sd(&totals)).map(|(a, s)| s * (1.0 - a).max(0.0).sqrt());
+    let alpha_if_deleted = all.iter()
+        .map(|&j| {
+            let rest: Vec<usize> = all.iter().copied().filter(|&c| c != j).collect();
+            alpha_of(&rest)
+        })
+        .collect();
+
+    Reliability {
+        n: matrix.len(),
+        excluded,
+        items: roots.iter().map(|&i| questions[i].id).collect(),
+        alpha,
+        sem,
+        full_score: roots.iter().map(|&i| tree.full_score(questions, i)).sum(),
+        alpha_if_deleted,
+    }
+}
+
+// Summary of the final scores
+#[derive(Debug, Clone, PartialEq)]
+pub struct Descriptive {
+    pub n: usize,
+    pub mean: f32,
+    pub median: f32,
+    // most frequent values; empty when no value occurs twice
+    pub modes: Vec<f32>,
+    pub sd: f32,
+    pub min: f32,
+    pub max: f32,
+    pub q1: f32,
+    pub q3: f32,
+}
+
+impl Descriptive {
+
+    pub fn iqr(&self) -> f32 {
+        self.q3 - self.q1
+    }
+}
+
+pub fn describe(scores: &[f32]) -> Option<Descriptive> {
+    let mut sorted = scores.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+    for v in sorted.iter() {
+        *counts.entry((v * 10000.0).round() as i64).or_default() += 1;
+    }
+    let top = counts.values().copied().max().unwrap_or(0);
+    let modes = counts.iter()
+        .filter(|(_, &c)| c == top && c > 1)
+        .map(|(&v, _)| v as f32 / 10000.0)
+        .collect();
+
+    Some(Descriptive {
+        n: sorted.len(),
+        mean: mean(&sorted)?,
+        median: quantile(&sorted, 0.5)?,
+        modes,
+        sd: sd(&sorted)?,
+        min: *sorted.first()?,
+        max: *sorted.last()?,
+        q1: quantile(&sorted, 0.25)?,
+        q3: quantile(&sorted, 0.75)?,
+    })
+}
+
+// Quantile of sorted values, interpolated between neighbours (as in spreadsheets)
+pub fn quantile(sorted: &[f32], p: f32) -> Option<f32> {
+    let last = sorted.len().checked_sub(1)?;
+    let pos = p.clamp(0.0, 1.0) * last as f32;
+    let (lo, frac) = (pos.floor() as usize, pos.fract());
+    let hi = (lo + 1).min(last);
+    Some(sorted[lo] + (sorted[hi] - sorted[lo]) * frac)
+}
+
+// Counts per bin of bin_width points from 0; the last bin includes its upper
+// end, so 100 falls into 95-100. Scores above 100 (uncapped bonus) extend the range.
+pub fn histogram(scores: &[f32], bin_width: f32) -> Vec<usize> {
+    let upper = scores.iter().copied().fold(100.0f32, f32::max);
+    let bins = ((upper / bin_width).ceil() as usize).max(1);
+    let mut h = vec![0usize; bins];
+    for &s in scores {
+        let idx = ((s.max(0.0) / bin_width) as usize).min(bins - 1);
+        h[idx] += 1;
+    }
+    h
+}
+
+pub fn mean(values: &[f32]) -> Option<f32> {
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+// Population standard deviation
+pub fn sd(values: &[f32]) -> Option<f32> {
+    let m = mean(values)?;
+    let var = values.iter().map(|v| (v - m).powi(2)).sum::<f32>() / values.len() as f32;
+    Some(var.sqrt())
+}
+
+// Pearson correlation; None when either side does not vary
+pub fn correlation(xs: &[f32], ys: &[f32]) -> Option<f32> {
+    let (mx, my) = (mean(xs)?, mean(ys)?);
+    let cov: f32 = xs.iter().zip(ys).map(|(x, y)| (x - mx) * (y - my)).sum();
+    let vx: f32 = xs.iter().map(|x| (x - mx).powi(2)).sum();
+    let vy: f32 = ys.iter().map(|y| (y - my).powi(2)).sum();
+    (vx > 0.0 && vy > 0.0).then(|| cov / (vx * vy).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Question, Score, Student};
+
+    #[test]
+    fn facility_discrimination_and_flags() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![
+            Question { id: 1, name: "Q1".into(), full_score: 10, ..Default::default() },
+            Question { id: 2, name: "Q2".into(), full_score: 10, ..Default::default() },
+        ];
+        // Q1 follows the total, Q2 is answered best by the weakest students
+        let marks = [(10.0, 1.0), (8.0, 2.0), (6.0, 3.0), (4.0, 3.0), (2.0, 4.0)];
+        let mut scores = Vec::new();
+        for (n, (a, b)) in marks.iter().enumerate() {
+            let sid = format!("S{n}");
+            cfg.students.push(Student { id: sid.clone(), name: String::new() });
+            scores.push(Score { student_id: sid.clone(), question_id: 1, score: Some(*a), ..Default::default() });
+            scores.push(Score { student_id: sid, question_id: 2, score: Some(*b), ..Default::default() });
+        }
+        cfg.scores = scores.into();
+
+        let stats = item_analysis(&cfg);
+        assert_eq!(stats[0].n, 5);
+        assert_eq!(stats[0].mean, Some(6.0));
+        assert_eq!(stats[0].facility, Some(0.6));
+        // 27% of 5 -> 1 student on each side: (10 - 2) / 10
+        assert_eq!(stats[0].discrimination, Some(0.8));
+        assert!(stats[0].point_biserial.unwrap() > 0.9);
+        assert!(!stats[0].is_flagged());
+
+        assert_eq!(stats[1].discrimination, Some(-0.3));
+        assert!(stats[1].is_flagged());
+        assert_eq!(stats[1].distribution, vec![(1.0, 1), (2.0, 1), (3.0, 2), (4.0, 1)]);
+    }
+
+    #[test]
+    fn alpha_and_alpha_if_deleted() {
+        let mut cfg = Config::new();
+        cfg.questions = (1..=3)
+            .map(|id| Question { id, name: format!("Q{id}"), full_score: 10, ..Default::default() })
+            .collect();
+        // Q1 and Q2 agree, Q3 is noise
+        let marks = [[9.0, 8.0, 1.0], [7.0, 7.0, 9.0], [5.0, 4.0, 2.0], [2.0, 3.0, 8.0]];
+        let mut scores = Vec::new();
+        for (n, row) in marks.iter().enumerate() {
+            let sid = format!("S{n}");
+            cfg.students.push(Student { id: sid.clone(), name: String::new() });
+            for (q, v) in row.iter().enumerate() {
+                scores.push(Score { student_id: sid.clone(), question_id: q as u32 + 1, score: Some(*v), ..Default::default() });
+            }
+        }
+        cfg.scores = scores.into();
+
+        let rel = reliability(&cfg);
+        assert_eq!((rel.n, rel.items.clone(), rel.full_score), (4, vec![1, 2, 3], 30));
+        let alpha = rel.alpha.unwrap();
+        // dropping the noisy item raises alpha, dropping a good one lowers it
+        assert!(rel.alpha_if_deleted[2].unwrap() > alpha);
+        assert!(rel.alpha_if_deleted[0].unwrap() < rel.alpha_if_deleted[2].unwrap());
+        assert!(rel.sem.unwrap() > 0.0);
+
+        // incomplete students are left out of the matrix
+        cfg.scores.set("S3", 3, None);
+        assert_eq!(reliability(&cfg).n, 3);
+    }
+
+    #[test]
+    fn bonus_questions_are_not_items() {
+        use crate::models::ScoreStatus;
+        let mut cfg = Config::new();
+        cfg.questions = (1..=3)
+            .map(|id| Question { id, name: format!("Q{id}"), full_score: 10, bonus: id == 3, ..Default::default() })
+            .collect();
+        let marks = [[9.0, 8.0], [7.0, 7.0], [5.0, 4.0], [2.0, 3.0]];
+        for (n, row) in marks.iter().enumerate() {
+            let sid = format!("S{n}");
+            cfg.students.push(Student { id: sid.clone(), name: String::new() });
+            cfg.scores.set(&sid, 1, Some(row[0]));
+            cfg.scores.set(&sid, 2, Some(row[1]));
+        }
+        // only S0 tried the bonus question
+        cfg.scores.set("S0", 3, Some(5.0));
+
+        let rel = reliability(&cfg);
+        assert_eq!((rel.n, rel.excluded, rel.items.clone(), rel.full_score), (4, 0, vec![1, 2], 20));
+        assert!(rel.alpha.is_some());
+
+        // blank bonus answers count as 0, as in the total
+        let bonus = &item_analysis(&cfg)[2];
+        assert_eq!((bonus.n, bonus.mean), (4, Some(1.25)));
+
+        // an exempt question takes the student out of the matrix, and says so
+        cfg.scores.set_status("S3", 2, Some(ScoreStatus::Exempt));
+        let rel = reliability(&cfg);
+        assert_eq!((rel.n, rel.excluded), (3, 1));
+    }
+
+    #[test]
+    fn descriptive_statistics_and_bins() {
+        let d = describe(&[70.0, 50.0, 90.0, 70.0, 60.0]).unwrap();
+        assert_eq!((d.n, d.mean, d.median), (5, 68.0, 70.0));
+        assert_eq!(d.modes, vec![70.0]);
+        assert_eq!((d.min, d.max, d.q1, d.q3, d.iqr()), (50.0, 90.0, 60.0, 70.0, 10.0));
+        assert_eq!(quantile(&[1.0, 2.0], 0.5), Some(1.5));
+        assert!(describe(&[]).is_none());
+        assert!(describe(&[1.0, 2.0]).unwrap().modes.is_empty());
+
+        let h = histogram(&[0.0, 9.5, 10.0, 100.0, 95.0], 10.0);
+        assert_eq!(h.len(), 10);
+        assert_eq!((h[0], h[1], h[9]), (2, 1, 2));
+        assert_eq!(histogram(&[110.0], 10.0).len(), 11);
+    }
+}
@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use anyhow::{Result, Context};
+use tokio::fs;
+use crate::backup;
+use crate::models::Config;
+
+// Autosave / crash-recovery journal.
+// The whole config is written atomically to a sidecar next to save_path
+// (or to the app data directory while the config has never been saved),
+// and the app data directory keeps a list of the recovery files, newest first,
+// so that they can be found again on the next launch, one file after another.
+
+const APP_DIR: &str = "grading_app";
+const RECOVERY_SUFFIX: &str = ".recovery";
+const UNSAVED_FILE: &str = "unsaved.json.recovery";
+// one recovery path per line; older versions wrote a single line
+const LIST_FILE: &str = "last_recovery.txt";
+const MAX_RECOVERIES: usize = 20;
+
+// Seconds without edits before an on-change autosave
+pub const IDLE_SECS: u64 = 3;
+// Longest time edits may stay unsaved while editing continues
+pub const INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovery {
+    pub path: PathBuf,
+    pub config: Config,
+    pub modified: SystemTime,
+}
+
+pub fn app_data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(APP_DIR)
+}
+
+// Sidecar for a saved config, app data file otherwise
+pub fn recovery_path(config: &Config) -> PathBuf {
+    match config.save_path.as_deref() {
+        Some(p) => {
+            let mut s = PathBuf::from(p).into_os_string();
+            s.push(RECOVERY_SUFFIX);
+            PathBuf::from(s)
+        }
+        None => app_data_dir().join(UNSAVED_FILE),
+    }
+}
+
+// Write the recovery file and remember where it is
+pub async fn write_recovery(config: &Config) -> Result<PathBuf> {
+    let path = recovery_path(config);
+    let data_dir = app_data_dir();
+    fs::create_dir_all(&data_dir)
+        .await
+        .with_context(|| format!("Failed to create directory: {:?}", data_dir))?;
+    let json = serde_json::to_string(config)
+        .context("Failed to serialize Config to JSON")?;
+    backup::write_atomic(&path, json.as_bytes()).await?;
+
+    let mut list = read_list(&data_dir).await;
+    if list.first() != Some(&path) {
+        remember(&mut list, path.clone());
+        write_list(&data_dir, &list).await?;
+    }
+    Ok(path)
+}
+
+// Remove the recovery file of a config once it has been saved or discarded
+pub async fn clear_recovery(config: &Config) {
+    let path = recovery_path(config);
+    let _ = fs::remove_file(&path).await;
+    let data_dir = app_data_dir();
+    let mut list = read_list(&data_dir).await;
+    list.retain(|p| *p != path);
+    let _ = write_list(&data_dir, &list).await;
+}
+
+// Newest recovery file of an earlier session that is newer than its saved config.
+// The others stay listed and come up once this one is restored and saved, or discarded.
+pub async fn find_recovery() -> Option<Recovery> {
+    for path in read_list(&app_data_dir()).await {
+        if let Some(r) = load_recovery(path).await {
+            return Some(r);
+        }
+    }
+    None
+}
+
+async fn load_recovery(path: PathBuf) -> Option<Recovery> {
+    let modified = modified_time(&path).await?;
+    let txt = fs::read_to_string(&path).await.ok()?;
+    let config = Config::from_json(&txt).ok()?;
+
+    let Some(saved_path) = config.save_path.as_deref().map(Path::new) else {
+        return Some(Recovery { path, config, modified });
+    };
+    if !is_newer(modified, modified_time(saved_path).await) {
+        return None;
+    }
+    // saved file already holds the same data
+    if let Ok(txt) = fs::read_to_string(saved_path).await {
+        if let Ok(mut saved) = Config::from_json(&txt) {
+            saved.save_path = config.save_path.clone();
+            if saved == config {
+                return None;
+            }
+        }
+    }
+    Some(Recovery { path, config, modified })
+}
+
+// Move a path to the front of the list of recovery files
+fn remember(list: &mut Vec<PathBuf>, path: PathBuf) {
+    list.retain(|p| *p != path);
+    list.insert(0, path);
+    list.truncate(MAX_RECOVERIES);
+}
+
+async fn read_list(data_dir: &Path) -> Vec<PathBuf> {
+    let txt = fs::read_to_string(data_dir.join(LIST_FILE)).await.unwrap_or_default();
+    txt.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+async fn write_list(data_dir: &Path, list: &[PathBuf]) -> Result<()> {
+    let txt: Vec<String> = list.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    backup::write_atomic(&data_dir.join(LIST_FILE), txt.join("\n").as_bytes()).await
+}
+
+fn is_newer(recovery: SystemTime, saved: Option<SystemTime>) -> bool {
+    saved.is_none_or(|t| recovery > t)
+}
+
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).await.ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn sidecar_next_to_save_path() {
+        let mut cfg = Config::new();
+        assert_eq!(recovery_path(&cfg), app_data_dir().join(UNSAVED_FILE));
+        cfg.save_path = Some("/data/exam.json".to_string());
+        assert_eq!(recovery_path(&cfg), PathBuf::from("/data/exam.json.recovery"));
+    }
+
+    #[test]
+    fn switching_files_keeps_earlier_recoveries() {
+        let mut list = vec![PathBuf::from("/data/a.json.recovery")];
+        remember(&mut list, PathBuf::from("/data/b.json.recovery"));
+        remember(&mut list, PathBuf::from("/data/a.json.recovery"));
+        assert_eq!(list, vec![PathBuf::from("/data/a.json.recovery"), PathBuf::from("/data/b.json.recovery")]);
+
+        for n in 0..MAX_RECOVERIES {
+            remember(&mut list, PathBuf::from(format!("/data/{n}.json.recovery")));
+        }
+        assert_eq!(list.len(), MAX_RECOVERIES);
+    }
+
+    #[test]
+    fn only_newer_recovery_is_offered() {
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        assert!(is_newer(t, None));
+        assert!(is_newer(t, Some(t - Duration::from_secs(1))));
+        assert!(!is_newer(t, Some(t)));
+    }
+}
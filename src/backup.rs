@@ -0,0 +1,206 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{Result, Context};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+// Atomic writes and rotating backups for saved configs.
+// Backups live in a `.backups` directory next to the saved file and are named
+// `<file name>.<UTC timestamp>.bak` with millisecond timestamps, so that they sort by age.
+// Names whose timestamp does not parse (e.g. backups of `<file name>.old`) are ignored.
+
+pub const KEEP: usize = 10;
+
+const BACKUP_DIR: &str = ".backups";
+const BACKUP_EXT: &str = ".bak";
+const TMP_EXT: &str = ".tmp";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupEntry {
+    pub path: PathBuf,
+    // "2026-10-18 15:30:00 UTC"
+    pub label: String,
+    pub size: u64,
+}
+
+pub fn backup_dir(target: &Path) -> PathBuf {
+    target.parent().unwrap_or(Path::new(".")).join(BACKUP_DIR)
+}
+
+// Write to a temp file in the same directory, fsync it and rename it over the target
+pub async fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(TMP_EXT);
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = fs::File::create(&tmp)
+        .await
+        .with_context(|| format!("Failed to create file: {:?}", tmp))?;
+    file.write_all(bytes)
+        .await
+        .with_context(|| format!("Failed to write file: {:?}", tmp))?;
+    file.sync_all()
+        .await
+        .with_context(|| format!("Failed to sync file: {:?}", tmp))?;
+    drop(file);
+
+    if let Err(e) = fs::rename(&tmp, path).await {
+        let _ = fs::remove_file(&tmp).await;
+        return Err(e).with_context(|| format!("Failed to replace file: {:?}", path));
+    }
+    Ok(())
+}
+
+// Copy the current target into the backup directory and drop the oldest backups
+pub async fn rotate(target: &Path, keep: usize) -> Result<()> {
+    if keep == 0 || fs::metadata(target).await.is_err() {
+        return Ok(());
+    }
+    let dir = backup_dir(target);
+    fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("Failed to create directory: {:?}", dir))?;
+
+    // saves within the same millisecond still get their own file
+    let mut now = SystemTime::now();
+    let backup = loop {
+        let path = dir.join(format!("{}.{}{}", file_name(target), file_stamp(now), BACKUP_EXT));
+        if fs::metadata(&path).await.is_err() {
+            break path;
+        }
+        now += Duration::from_millis(1);
+    };
+    fs::copy(target, &backup)
+        .await
+        .with_context(|| format!("Failed to back up {:?} to {:?}", target, backup))?;
+
+    for old in list_backups(target).await.into_iter().skip(keep) {
+        let _ = fs::remove_file(&old.path).await;
+    }
+    Ok(())
+}
+
+// Backups of a target file, newest first
+pub async fn list_backups(target: &Path) -> Vec<BackupEntry> {
+    let prefix = format!("{}.", file_name(target));
+    let Ok(mut rd) = fs::read_dir(backup_dir(target)).await else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    while let Ok(Some(entry)) = rd.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(stamp) = name.strip_prefix(&prefix).and_then(|s| s.strip_suffix(BACKUP_EXT)) else {
+            continue;
+        };
+        let Some(label) = stamp_label(stamp) else {
+            continue;
+        };
+        let size = entry.metadata().await.map(|m| m.len()).unwrap_or(0);
+        out.push((stamp.to_string(), BackupEntry { path: entry.path(), label, size }));
+    }
+    // a second-only stamp (older versions) sorts before the same second with millis
+    out.sort_by(|a, b| b.0.cmp(&a.0));
+    out.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// "20261018-153000-123" (UTC, with milliseconds)
+fn file_stamp(t: SystemTime) -> String {
+    let since = t.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let s = secs % 86_400;
+    format!(
+        "{y:04}{m:02}{d:02}-{:02}{:02}{:02}-{:03}",
+        s / 3600, s % 3600 / 60, s % 60, since.subsec_millis(),
+    )
+}
+
+// "20261018-153000-123" or the older "20261018-153000" -> "2026-10-18 15:30:00 UTC";
+// None for anything else
+fn stamp_label(stamp: &str) -> Option<String> {
+    let b = stamp.as_bytes();
+    let digits = |r: std::ops::Range<usize>| b[r].iter().all(u8::is_ascii_digit);
+    let valid = match b.len() {
+        15 => b[8] == b'-' && digits(0..8) && digits(9..15),
+        19 => b[8] == b'-' && b[15] == b'-' && digits(0..8) && digits(9..15) && digits(16..19),
+        _ => false,
+    };
+    if !valid {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{} {}:{}:{} UTC",
+        &stamp[0..4], &stamp[4..6], &stamp[6..8],
+        &stamp[9..11], &stamp[11..13], &stamp[13..15],
+    ))
+}
+
+// Days since 1970-01-01 to (year, month, day), proleptic Gregorian
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        let t = UNIX_EPOCH + Duration::from_millis(1_792_337_400_042); // 2026-10-18 15:30:00.042 UTC
+        assert_eq!(file_stamp(t), "20261018-153000-042");
+        assert_eq!(stamp_label("20261018-153000-042").as_deref(), Some("2026-10-18 15:30:00 UTC"));
+        assert_eq!(stamp_label("20261018-153000").as_deref(), Some("2026-10-18 15:30:00 UTC"));
+        assert_eq!(stamp_label("old.20261018-153000"), None);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[tokio::test]
+    async fn save_rotates_and_prunes() {
+        let dir = std::env::temp_dir().join(format!("grading_app_backup_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir).await;
+        fs::create_dir_all(&dir).await.unwrap();
+        let target = dir.join("exam.json");
+
+        write_atomic(&target, b"v1").await.unwrap();
+        rotate(&target, 2).await.unwrap();
+        assert_eq!(list_backups(&target).await.len(), 1);
+
+        // backups of "exam.json.old" share the prefix but are not ours
+        fs::write(backup_dir(&target).join("exam.json.old.20991231-000000.bak"), b"x").await.unwrap();
+        assert_eq!(list_backups(&target).await.len(), 1);
+
+        // fake older backups, the oldest has to go
+        for stamp in ["20000101-000000", "20000102-000000"] {
+            let p = backup_dir(&target).join(format!("exam.json.{stamp}.bak"));
+            fs::write(p, b"old").await.unwrap();
+        }
+        rotate(&target, 2).await.unwrap();
+        let backups = list_backups(&target).await;
+        assert_eq!(backups.len(), 2);
+        assert!(fs::metadata(backup_dir(&target).join("exam.json.old.20991231-000000.bak")).await.is_ok());
+        assert!(!backups.iter().any(|b| b.label.starts_with("2000-01-01")));
+
+        write_atomic(&target, b"v2").await.unwrap();
+        assert_eq!(fs::read(&target).await.unwrap(), b"v2");
+        assert!(fs::metadata(dir.join("exam.json.tmp")).await.is_err());
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+}
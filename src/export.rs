@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context};
+use dioxus::prelude::*;
+use tokio::fs;
+use crate::{analysis, grading};
+use crate::models::{Config, TableRow};
+
+// Grade matrix export (CSV / TSV).
+// Rows come from the grading engine, so the file matches MatrixTable exactly.
+// Reliability statistics go to a file of their own so that the matrix stays rectangular.
+
+const UTF8_BOM: &str = "\u{feff}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+}
+
+impl ExportFormat {
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+        }
+    }
+
+    fn delimiter(self) -> u8 {
+        match self {
+            ExportFormat::Csv => b',',
+            ExportFormat::Tsv => b'\t',
+        }
+    }
+}
+
+// Header + one line per student, questions in configured order
+pub fn grade_matrix(config: &Config) -> Vec<Vec<String>> {
+    let results = grading::evaluate(config);
+    let rows = grading::table_rows(config, &results);
+
+    let mut out = Vec::with_capacity(rows.len() + 1);
+    let mut header = vec!["id".to_string(), "name".to_string()];
+    header.extend(config.questions.iter().map(|q| q.name.clone()));
+    header.push("adjustment".to_string());
+    header.push("score".to_string());
+    header.push("rating".to_string());
+    out.push(header);
+
+    out.extend(rows.into_iter().map(|r: TableRow| {
+        let mut line = vec![r.student_id, r.student_name];
+        line.extend(r.scores);
+        line.push(r.adjustment);
+        line.push(r.final_display);
+        line.push(r.rating);
+        line
+    }));
+    out
+}
+
+// Reliability of the exam: alpha-if-deleted per question, then the summary values.
+// Two columns throughout.
+pub fn reliability_rows(config: &Config) -> Vec<Vec<String>> {
+    let rel = analysis::reliability(config);
+    let fixed = |v: Option<f32>| v.map(|v| format!("{v:.3}")).unwrap_or_default();
+
+    let mut out = vec![vec!["item".to_string(), "alpha_if_deleted".to_string()]];
+    out.extend(rel.items.iter().enumerate().map(|(j, id)| {
+        let name = config.questions.iter().find(|q| q.id == *id).map(|q| q.name.clone()).unwrap_or_default();
+        vec![name, fixed(rel.alpha_if_deleted[j])]
+    }));
+    out.push(vec!["cronbach_alpha".to_string(), fixed(rel.alpha)]);
+    out.push(vec!["sem".to_string(), fixed(rel.sem)]);
+    out.push(vec!["n".to_string(), rel.n.to_string()]);
+    out.push(vec!["excluded".to_string(), rel.excluded.to_string()]);
+    out
+}
+
+// Encode rows as UTF-8 text with BOM so that Excel detects the encoding
+pub fn encode(rows: &[Vec<String>], format: ExportFormat) -> Result<Vec<u8>> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(format.delimiter())
+        .flexible(true)
+        .from_writer(UTF8_BOM.as_bytes().to_vec());
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.into_inner().context("Failed to flush export buffer")
+}
+
+async fn write_rows(rows: &[Vec<String>], format: ExportFormat, path: &Path) -> Result<()> {
+    let bytes = encode(rows, format)?;
+    fs::write(path, bytes)
+        .await
+        .with_context(|| format!("Failed to write file: {:?}", path))?;
+    Ok(())
+}
+
+// Open filedialog and export the grade matrix
+pub fn export_as(config: Signal<Config>, format: ExportFormat, msg: Signal<String>) {
+    save_rows_as(config, grade_matrix, format, "grades", msg);
+}
+
+// Open filedialog and export the reliability statistics
+pub fn export_reliability_as(config: Signal<Config>, format: ExportFormat, msg: Signal<String>) {
+    save_rows_as(config, reliability_rows, format, "reliability", msg);
+}
+
+fn save_rows_as(
+    config: Signal<Config>,
+    rows: fn(&Config) -> Vec<Vec<String>>,
+    format: ExportFormat,
+    default_name: &'static str,
+    mut msg: Signal<String>,
+) {
+    spawn(async move {
+        let ext = format.extension();
+        let handle = rfd::AsyncFileDialog::new()
+            .add_filter(ext.to_uppercase(), &[ext])
+            .set_file_name(format!("{default_name}.{ext}"))
+            .save_file()
+            .await;
+
+        let Some(handle) = handle else {
+            return;
+        };
+
+        let path: PathBuf = handle.path().to_path_buf();
+        let cfg_snapshot = config();
+        match write_rows(&rows(&cfg_snapshot), format, &path).await {
+            Ok(()) => msg.set(format!("Exported: {:?}", path)),
+            Err(e) => msg.set(format!("Export failed: {:#}", e)),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Question, Rating, Score, Student};
+
+    #[test]
+    fn matrix_with_blank_cells_and_rating() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![
+            Question { id: 2, name: "Q2".into(), full_score: 10, ..Default::default() },
+            Question { id: 1, name: "Q1".into(), full_score: 10, ..Default::default() },
+        ];
+        cfg.students = vec![
+            Student { id: "A1".into(), name: "山田".into() },
+            Student { id: "A2".into(), name: "佐藤, 花子".into() },
+        ];
+        cfg.scores = vec![
+            Score { student_id: "A1".into(), question_id: 1, score: Some(8.0), ..Default::default() },
+            Score { student_id: "A1".into(), question_id: 2, score: Some(6.0), ..Default::default() },
+            Score { student_id: "A2".into(), question_id: 1, score: Some(5.0), ..Default::default() },
+        ].into();
+        cfg.ratings = vec![Rating { label: "A".into(), min_score: 70, quota: None }];
+
+        let csv = encode(&grade_matrix(&cfg), ExportFormat::Csv).unwrap();
+        let text = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            text,
+            "\u{feff}id,name,Q2,Q1,adjustment,score,rating\nA1,山田,6,8,,70,A\nA2,\"佐藤, 花子\",,5,,,\n",
+        );
+
+        let tsv = encode(&grade_matrix(&cfg), ExportFormat::Tsv).unwrap();
+        assert!(String::from_utf8(tsv).unwrap().contains("A1\t山田\t6\t8\t\t70\tA\n"));
+
+        // one completed student: no alpha yet, and every row has two cells
+        let rel = reliability_rows(&cfg);
+        assert_eq!(rel[0], vec!["item", "alpha_if_deleted"]);
+        assert_eq!(rel[1], vec!["Q2", ""]);
+        assert_eq!(rel[3], vec!["cronbach_alpha", ""]);
+        assert_eq!(rel[5], vec!["n", "1"]);
+        assert!(rel.iter().all(|r| r.len() == 2));
+    }
+}
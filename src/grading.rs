@@ -0,0 +1,763 @@
+use std::collections::HashMap;
+use crate::models::{CapPolicy, ChoiceRule, Config, Question, Rating, RatingMode, Rounding, ScoreStatus, Student, StatusScoring, TableRow, TiePolicy};
+
+// Headless grading engine.
+// Every view (MatrixTable, RatingPage, exports) goes through these functions
+// so that the same student always gets the same number everywhere.
+//
+// Order of operations for the final percentage of a student:
+//   1. question scores: entered values (min_score allows negatives), status codes per policy
+//   2. groups sum their parts (only N of them under a choice rule);
+//      weighted mean over top-level questions, bonus questions add to the sum
+//      but not to the denominator
+//   3. cap policy (at most 100)
+//   4. late penalty: x (1 - late_penalty / 100)
+//   5. fixed deduction in percentage points
+//   6. floor at 0
+//   7. academic misconduct: 0
+//   8. rounding per Config.rounding; rating, display and export all use this value
+//
+// Ratings: in absolute mode min_score is the cut-off. In relative mode the
+// cut-offs come from the distribution of the final scores in the statistics,
+// see rating_cutoffs.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudentResult {
+    pub student_id: String,
+    pub student_name: String,
+    // scores in configured question order (None = 未入力);
+    // a question with parts holds the sum of its parts
+    pub scores: Vec<Option<f32>>,
+    // status codes of the cells, same order as scores
+    pub statuses: Vec<Option<ScoreStatus>>,
+    // questions left out by a best-N / N-chosen rule, same order as scores
+    pub dropped: Vec<bool>,
+    // full score of each question for this student, same order as scores;
+    // a question with parts counts only its picked, not excluded parts
+    pub fulls: Vec<f32>,
+    // weighted percentage after step 3, before the student's adjustments
+    pub raw_percent: Option<f32>,
+    // final percentage before rounding (None = 未完了)
+    pub percent: Option<f32>,
+    // rounding setting the final score is taken with
+    pub rounding: Rounding,
+    // counted by the 入力済み counter (status policy applied)
+    pub complete: bool,
+    // included in rating statistics (status policy applied)
+    pub in_stats: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingStats {
+    pub label: String,
+    // effective cut-off (f32::INFINITY = nobody can get the rating)
+    pub cutoff: f32,
+    pub count: usize,
+    pub ratio: f32,
+}
+
+// Parent/part structure of Config.questions, by index.
+// A question with parts is a group: its score and full score are the sums of its parts,
+// and only top-level questions carry weight in the final percentage.
+// Links to missing parents or cyclic links are ignored (the question becomes top-level).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hierarchy {
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+}
+
+impl Hierarchy {
+
+    pub fn new(questions: &[Question]) -> Hierarchy {
+        let mut index: HashMap<u32, usize> = HashMap::new();
+        for (i, q) in questions.iter().enumerate() {
+            index.entry(q.id).or_insert(i);
+        }
+        let raw: Vec<Option<usize>> = questions.iter()
+            .map(|q| q.parent_id.and_then(|p| index.get(&p).copied()))
+            .collect();
+
+        // drop links that end up in a cycle
+        let parents: Vec<Option<usize>> = (0..questions.len())
+            .map(|i| {
+                let p = raw[i]?;
+                let mut cur = Some(p);
+                let mut steps = 0;
+                while let Some(c) = cur {
+                    if c == i {
+                        return None;
+                    }
+                    // ran into a cycle that does not contain i
+                    if steps > questions.len() {
+                        break;
+                    }
+                    cur = raw[c];
+                    steps += 1;
+                }
+                Some(p)
+            })
+            .collect();
+
+        let mut children = vec![Vec::new(); questions.len()];
+        for (i, p) in parents.iter().enumerate() {
+            if let Some(p) = p {
+                children[*p].push(i);
+            }
+        }
+        Hierarchy { parents, children }
+    }
+
+    pub fn parent(&self, i: usize) -> Option<usize> {
+        self.parents.get(i).copied().flatten()
+    }
+
+    pub fn children(&self, i: usize) -> &[usize] {
+        self.children.get(i).map(|c| c.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn is_group(&self, i: usize) -> bool {
+        !self.children(i).is_empty()
+    }
+
+    pub fn depth(&self, i: usize) -> usize {
+        let mut d = 0;
+        let mut cur = self.parent(i);
+        while let Some(p) = cur {
+            d += 1;
+            cur = self.parent(p);
+        }
+        d
+    }
+
+    // Whether any ancestor of the question is in the set
+    pub fn has_ancestor_in(&self, i: usize, set: &[usize]) -> bool {
+        let mut cur = self.parent(i);
+        while let Some(p) = cur {
+            if set.contains(&p) {
+                return true;
+            }
+            cur = self.parent(p);
+        }
+        false
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.parents.len()).filter(|&i| self.parents[i].is_none())
+    }
+
+    // Questions that take a score input
+    pub fn leaves(&self) -> Vec<usize> {
+        (0..self.parents.len()).filter(|&i| !self.is_group(i)).collect()
+    }
+
+    // Full score of a question; the sum of its parts for a group,
+    // or of its N largest parts under a choice rule
+    pub fn full_score(&self, questions: &[Question], i: usize) -> u32 {
+        if !self.is_group(i) {
+            return questions[i].full_score;
+        }
+        let mut parts: Vec<u32> = self.children(i).iter().map(|&c| self.full_score(questions, c)).collect();
+        if let Some(choice) = questions[i].choice {
+            parts.sort_unstable_by(|a, b| b.cmp(a));
+            parts.truncate(choice.count as usize);
+        }
+        parts.iter().sum()
+    }
+}
+
+impl StudentResult {
+
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    // Final score used for display, rating and export
+    pub fn final_score(&self) -> Option<f32> {
+        self.percent.map(|p| self.rounding.apply(p))
+    }
+
+    // Final score as shown in the grid and written to exports
+    pub fn final_display(&self) -> Option<String> {
+        self.percent.map(|p| self.rounding.format(p))
+    }
+}
+
+// Evaluate every student in roster order
+pub fn evaluate(config: &Config) -> Vec<StudentResult> {
+    config.students.iter()
+        .map(|s| evaluate_student(config, s))
+        .collect()
+}
+
+// Evaluate a single student
+pub fn student_result(config: &Config, student_id: &str) -> Option<StudentResult> {
+    config.students.iter()
+        .find(|s| s.id == student_id)
+        .map(|s| evaluate_student(config, s))
+}
+
+// Number of students whose every question is filled (status policy applied)
+pub fn completed_count(results: &[StudentResult]) -> usize {
+    results.iter().filter(|r| r.is_complete()).count()
+}
+
+// Final scores of the students that go into the statistics
+pub fn final_scores(results: &[StudentResult]) -> Vec<f32> {
+    results.iter()
+        .filter(|r| r.in_stats)
+        .filter_map(|r| r.final_score())
+        .collect()
+}
+
+// Display rows for the grid and exports; blank cells for 未入力
+pub fn table_rows(config: &Config, results: &[StudentResult]) -> Vec<TableRow> {
+    let cutoffs = rating_cutoffs(config, &final_scores(results));
+    results.iter().map(|r| {
+        let final_score = r.final_score();
+        TableRow {
+            student_id: r.student_id.clone(),
+            student_name: r.student_name.clone(),
+            scores: r.scores.iter().zip(&r.statuses)
+                .map(|(sc, st)| match st {
+                    Some(st) => st.code().to_string(),
+                    None => sc.map(format_score).unwrap_or_default(),
+                })
+                .collect(),
+            dropped: r.dropped.clone(),
+            adjustment: config.adjustment(&r.student_id).map(|a| a.summary()).unwrap_or_default(),
+            final_display: r.final_display().unwrap_or_default(),
+            rating: final_score
+                .and_then(|v| assign_rating(v, &config.ratings, &cutoffs))
+                .map(|i| config.ratings[i].label.clone())
+                .unwrap_or_default(),
+        }
+    }).collect()
+}
+
+// Cut-off score of every rating, same order as config.ratings.
+// Relative mode goes down the ratings by min_score (list order on a tie), like
+// absolute mode ranks them. A rating with a quota takes at most quota% of all
+// scores, counted below the ratings above it; its min_score stays a floor.
+// A rating without a quota keeps its min_score as the cut-off.
+// Tie policy: when equal scores straddle the quota, Down gives the whole group
+// the lower rating (the quota is never exceeded), Up gives it the higher one.
+pub fn rating_cutoffs(config: &Config, scores: &[f32]) -> Vec<f32> {
+    let ratings = &config.ratings;
+    let mut cutoffs: Vec<f32> = ratings.iter().map(|r| r.min_score as f32).collect();
+    if config.rating_mode == RatingMode::Absolute {
+        return cutoffs;
+    }
+
+    // highest first
+    let mut sorted = scores.to_vec();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let n = sorted.len();
+    let count_at_least = |t: f32| sorted.iter().take_while(|&&s| s >= t).count();
+
+    let mut order: Vec<usize> = (0..ratings.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(ratings[i].min_score));
+
+    let mut prev = f32::INFINITY;
+    for i in order {
+        let floor = ratings[i].min_score as f32;
+        let Some(quota) = ratings[i].quota else {
+            cutoffs[i] = floor;
+            prev = floor;
+            continue;
+        };
+        let taken = count_at_least(prev);
+        let limit = taken + (quota.max(0.0) / 100.0 * n as f32 + 1e-4).floor() as usize;
+        let cut = match config.tie_policy {
+            // lowest score that keeps the count within the limit
+            TiePolicy::Down => sorted.iter().rev().copied()
+                .find(|&s| s < prev && count_at_least(s) <= limit)
+                .unwrap_or(prev),
+            // score of the last student within the limit, with everyone tied to it
+            TiePolicy::Up if limit > taken && taken < n => sorted[limit.min(n) - 1],
+            TiePolicy::Up => prev,
+        };
+        cutoffs[i] = cut.max(floor);
+        prev = cutoffs[i];
+    }
+    cutoffs
+}
+
+// Pick the rating with the highest cut-off that the score reaches;
+// equal cut-offs go to the higher min_score, then to the earlier rating
+pub fn assign_rating(score: f32, ratings: &[Rating], cutoffs: &[f32]) -> Option<usize> {
+    ratings.iter()
+        .zip(cutoffs)
+        .enumerate()
+        .filter(|(_, (_, &c))| score >= c)
+        .max_by(|(i, (a, ca)), (j, (b, cb))| {
+            ca.total_cmp(cb)
+                .then(a.min_score.cmp(&b.min_score))
+                .then(j.cmp(i))
+        })
+        .map(|(i, _)| i)
+}
+
+pub fn compute_rating_stats(
+    scores: &[f32],
+    ratings: &[Rating],
+    cutoffs: &[f32],
+) -> Vec<RatingStats> {
+
+    let mut counts = vec![0usize; ratings.len()];
+
+    for &s in scores {
+        if let Some(i) = assign_rating(s, ratings, cutoffs) {
+            counts[i] += 1;
+        }
+    }
+
+    let total = scores.len().max(1);
+
+    ratings.iter().enumerate().map(|(i, r)| {
+        RatingStats {
+            label: r.label.clone(),
+            cutoff: cutoffs[i],
+            count: counts[i],
+            ratio: counts[i] as f32 / total as f32,
+        }
+    }).collect()
+}
+
+// Parts counted under a choice rule; fewer than n when not enough are answered
+fn pick_parts(rule: ChoiceRule, n: usize, parts: &[usize], scores: &[Option<f32>], fulls: &[f32]) -> Vec<usize> {
+    let answered = parts.iter().copied().filter(|&c| scores[c].is_some());
+    let mut picked: Vec<usize> = match rule {
+        ChoiceRule::Chosen => answered.take(n).collect(),
+        ChoiceRule::Best => {
+            let rate = |c: usize| {
+                let v = scores[c].unwrap_or(0.0);
+                if fulls[c] > 0.0 { v / fulls[c] } else { v }
+            };
+            let mut all: Vec<usize> = answered.collect();
+            // stable: ties keep question order
+            all.sort_by(|&a, &b| rate(b).total_cmp(&rate(a)));
+            all.truncate(n);
+            all
+        }
+    };
+    picked.sort_unstable();
+    picked
+}
+
+// Score for display: no trailing zeros, float noise from steps like 0.1 removed
+pub fn format_score(score: f32) -> String {
+    let v = (score * 10000.0).round() / 10000.0;
+    format!("{}", v + 0.0)
+}
+
+fn evaluate_student(
+    config: &Config,
+    student: &Student,
+) -> StudentResult {
+    let questions = &config.questions;
+    let policy = &config.status_policy;
+    let tree = Hierarchy::new(questions);
+
+    let statuses: Vec<Option<ScoreStatus>> = questions.iter()
+        .map(|q| config.scores.status(&student.id, q.id))
+        .collect();
+
+    // leaves first, then groups bottom-up; excluded questions drop out of
+    // their group and of the weighting
+    let mut scores: Vec<Option<f32>> = Vec::with_capacity(questions.len());
+    let mut fulls: Vec<f32> = Vec::with_capacity(questions.len());
+    let mut excluded = vec![false; questions.len()];
+    for (i, q) in questions.iter().enumerate() {
+        fulls.push(q.full_score as f32);
+        scores.push(match statuses[i].map(|st| policy.rule(st).scoring) {
+            None => config.scores.value(&student.id, q.id),
+            Some(StatusScoring::Zero) => Some(0.0),
+            Some(StatusScoring::Blank) => None,
+            Some(StatusScoring::Exclude) => {
+                excluded[i] = true;
+                None
+            }
+        });
+    }
+    let mut order: Vec<usize> = (0..questions.len()).filter(|&i| tree.is_group(i)).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(tree.depth(i)));
+    let mut dropped = vec![false; questions.len()];
+    for i in order {
+        let mut parts: Vec<usize> = tree.children(i).iter().copied().filter(|&c| !excluded[c]).collect();
+        excluded[i] = parts.is_empty();
+        if let Some(choice) = questions[i].choice {
+            let n = (choice.count as usize).min(parts.len());
+            let picked = pick_parts(choice.rule, n, &parts, &scores, &fulls);
+            if picked.len() == n {
+                for c in parts.iter().filter(|c| !picked.contains(c)) {
+                    dropped[*c] = true;
+                }
+                parts = picked;
+            } else {
+                // too few answers yet: the group stays open
+                scores[i] = None;
+                let mut part_fulls: Vec<f32> = parts.iter().map(|&c| fulls[c]).collect();
+                part_fulls.sort_by(|a, b| b.total_cmp(a));
+                fulls[i] = part_fulls.iter().take(n).sum();
+                continue;
+            }
+        }
+        scores[i] = parts.iter().map(|&c| scores[c]).sum::<Option<f32>>();
+        fulls[i] = parts.iter().map(|&c| fulls[c]).sum();
+    }
+    // parts of a dropped group are dropped too
+    for i in 0..questions.len() {
+        let mut cur = tree.parent(i);
+        while let Some(p) = cur {
+            if dropped[p] {
+                dropped[i] = true;
+                break;
+            }
+            cur = tree.parent(p);
+        }
+    }
+
+    // bonus questions add to the sum but not to the denominator
+    let total_weight: f32 = tree.roots()
+        .filter(|&i| !excluded[i] && !questions[i].bonus)
+        .map(|i| questions[i].weight)
+        .sum();
+    let mut filled = true;
+    let mut weighted_rate_sum: f32 = 0.0;
+
+    for i in tree.roots().filter(|&i| !excluded[i]) {
+        let full = fulls[i];
+        match scores[i] {
+            Some(scv) if full > 0.0 => {
+                let rate = scv / full;
+                weighted_rate_sum += rate * questions[i].weight;
+            }
+            // 加点問題は未入力でも 0 点扱い
+            _ if questions[i].bonus => {}
+            // full_score=0 は未入力扱い
+            _ => filled = false,
+        }
+    }
+
+    let raw_percent = (filled && total_weight > 0.0)
+        .then(|| weighted_rate_sum / total_weight * 100.0)
+        .map(|p| match config.cap_policy {
+            CapPolicy::Cap100 => p.min(100.0),
+            CapPolicy::Uncapped => p,
+        });
+
+    let adjustment = config.adjustment(&student.id);
+    let percent = match adjustment {
+        Some(a) if a.misconduct => Some(0.0),
+        Some(a) => raw_percent.map(|p| {
+            let late = p * (1.0 - a.late_penalty / 100.0);
+            (late - a.deduction).max(0.0)
+        }),
+        None => raw_percent.map(|p| p.max(0.0)),
+    };
+
+    let rules: Vec<_> = statuses.iter().flatten().map(|&st| policy.rule(st)).collect();
+    StudentResult {
+        student_id: student.id.clone(),
+        student_name: student.name.clone(),
+        scores,
+        statuses,
+        dropped,
+        fulls,
+        raw_percent,
+        percent,
+        rounding: config.rounding,
+        complete: percent.is_some() && rules.iter().all(|r| r.complete),
+        in_stats: rules.iter().all(|r| r.in_stats),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Question, RoundingMode, Score};
+
+    fn question(id: u32, full_score: u32, weight: f32) -> Question {
+        Question { id, name: format!("Q{id}"), full_score, weight, ..Default::default() }
+    }
+
+    fn student(id: &str) -> Student {
+        Student { id: id.to_string(), name: String::new() }
+    }
+
+    fn score(student_id: &str, question_id: u32, score: Option<f32>) -> Score {
+        Score { student_id: student_id.to_string(), question_id, score, ..Default::default() }
+    }
+
+    fn rating(label: &str, min_score: u32) -> Rating {
+        Rating { label: label.to_string(), min_score, quota: None }
+    }
+
+    fn sample() -> Config {
+        let mut cfg = Config::new();
+        cfg.questions = vec![question(1, 10, 1.0), question(2, 20, 3.0)];
+        cfg.students = vec![student("S1"), student("S2"), student("S3")];
+        cfg.scores = vec![
+            score("S1", 1, Some(10.0)),
+            score("S1", 2, Some(10.0)),
+            score("S2", 1, Some(5.0)),
+            score("S3", 1, Some(6.0)),
+            score("S3", 2, None),
+        ].into();
+        cfg
+    }
+
+    #[test]
+    fn weighted_percentage() {
+        let results = evaluate(&sample());
+        // (1.0 * 1 + 0.5 * 3) / 4 = 62.5%
+        assert_eq!(results[0].percent, Some(62.5));
+        assert_eq!(results[0].final_score(), Some(63.0));
+        assert_eq!(results[0].scores, vec![Some(10.0), Some(10.0)]);
+    }
+
+    #[test]
+    fn missing_scores_are_incomplete() {
+        let results = evaluate(&sample());
+        assert!(!results[1].is_complete());
+        assert!(!results[2].is_complete());
+        assert_eq!(results[1].scores, vec![Some(5.0), None]);
+        assert_eq!(completed_count(&results), 1);
+        assert_eq!(final_scores(&results), vec![63.0]);
+    }
+
+    #[test]
+    fn status_codes_follow_the_policy() {
+        let mut cfg = sample();
+        cfg.scores.set_status("S2", 2, Some(ScoreStatus::Absent));
+        let r = student_result(&cfg, "S2").unwrap();
+        // absent = 0 points: (0.5 * 1 + 0 * 3) / 4
+        assert_eq!(r.percent, Some(12.5));
+        assert_eq!(table_rows(&cfg, std::slice::from_ref(&r))[0].scores, vec!["5", "欠"]);
+
+        // exempt: Q2 dropped, Q1 alone carries the weight
+        cfg.scores.set_status("S2", 2, Some(ScoreStatus::Exempt));
+        assert_eq!(student_result(&cfg, "S2").unwrap().percent, Some(50.0));
+
+        // not submitted: scored, counted as complete, left out of the statistics
+        cfg.scores.set_status("S2", 2, Some(ScoreStatus::NotSubmitted));
+        let results = evaluate(&cfg);
+        assert!(results[1].is_complete());
+        assert_eq!(completed_count(&results), 2);
+        assert_eq!(final_scores(&results), vec![63.0]);
+
+        cfg.status_policy.not_submitted.scoring = StatusScoring::Blank;
+        assert!(!student_result(&cfg, "S2").unwrap().is_complete());
+    }
+
+    #[test]
+    fn bonus_points_and_cap() {
+        let mut cfg = sample();
+        cfg.questions.push(Question { bonus: true, ..question(9, 10, 1.0) });
+        // blank bonus counts as 0
+        assert_eq!(student_result(&cfg, "S1").unwrap().percent, Some(62.5));
+
+        // (1.0 * 1 + 0.5 * 3 + 1.0 * 1) / 4 = 87.5%
+        cfg.scores.set("S1", 9, Some(10.0));
+        assert_eq!(student_result(&cfg, "S1").unwrap().percent, Some(87.5));
+
+        // over full on a normal question, above 100 only when uncapped
+        cfg.scores.set("S1", 2, Some(24.0));
+        assert_eq!(student_result(&cfg, "S1").unwrap().percent, Some(100.0));
+        cfg.cap_policy = CapPolicy::Uncapped;
+        // 1.0 * 1 + 1.2 * 3 + 1.0 * 1 = 5.6 -> 140%
+        let p = student_result(&cfg, "S1").unwrap().percent.unwrap();
+        assert!((p - 140.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn negative_marks_and_adjustments_in_order() {
+        let mut cfg = sample();
+        cfg.questions[0].min_score = -5.0;
+        cfg.scores.set("S1", 1, Some(-5.0));
+        // (-0.5 * 1 + 0.5 * 3) / 4 = 25%
+        assert_eq!(student_result(&cfg, "S1").unwrap().percent, Some(25.0));
+
+        cfg.scores.set("S1", 1, Some(10.0));
+        cfg.edit_adjustment("S1", |a| {
+            a.late_penalty = 20.0;
+            a.deduction = 5.0;
+        });
+        // 62.5 * 0.8 - 5 = 45
+        let r = student_result(&cfg, "S1").unwrap();
+        assert_eq!(r.raw_percent, Some(62.5));
+        assert_eq!(r.percent, Some(45.0));
+        assert_eq!(table_rows(&cfg, std::slice::from_ref(&r))[0].adjustment, "遅延-20% / -5");
+
+        cfg.edit_adjustment("S1", |a| a.deduction = 80.0);
+        assert_eq!(student_result(&cfg, "S1").unwrap().percent, Some(0.0));
+
+        cfg.edit_adjustment("S3", |a| a.misconduct = true);
+        assert_eq!(student_result(&cfg, "S3").unwrap().final_score(), Some(0.0));
+
+        cfg.edit_adjustment("S3", |a| a.misconduct = false);
+        assert!(cfg.adjustment("S3").is_none());
+    }
+
+    #[test]
+    fn best_and_chosen_parts() {
+        use crate::models::Choice;
+        let mut cfg = sample();
+        // Q3: any 2 of 31..33, weight 4
+        cfg.questions.push(Question { choice: Some(Choice { rule: ChoiceRule::Best, count: 2 }), ..question(3, 0, 4.0) });
+        for id in [31, 32, 33] {
+            cfg.questions.push(Question { parent_id: Some(3), ..question(id, 10, 1.0) });
+        }
+        assert_eq!(Hierarchy::new(&cfg.questions).full_score(&cfg.questions, 2), 20);
+
+        cfg.scores.set("S1", 31, Some(4.0));
+        assert!(!student_result(&cfg, "S1").unwrap().is_complete());
+
+        cfg.scores.set("S1", 32, Some(9.0));
+        cfg.scores.set("S1", 33, Some(7.0));
+        let r = student_result(&cfg, "S1").unwrap();
+        assert_eq!(r.scores[2], Some(16.0));
+        assert_eq!(r.dropped, vec![false, false, false, true, false, false]);
+        // (1.0 * 1 + 0.5 * 3 + 0.8 * 4) / 8 = 71.25%
+        assert_eq!(r.percent, Some(71.25));
+
+        // chosen: the first two answered, even if the third is better
+        cfg.questions[2].choice = Some(Choice { rule: ChoiceRule::Chosen, count: 2 });
+        let r = student_result(&cfg, "S1").unwrap();
+        assert_eq!(r.scores[2], Some(13.0));
+        assert!(r.dropped[5]);
+
+        // the group's full score follows the parts this student picked
+        cfg.questions[3].full_score = 5;
+        let r = student_result(&cfg, "S1").unwrap();
+        assert_eq!(r.fulls[2], 15.0);
+        assert_eq!(Hierarchy::new(&cfg.questions).full_score(&cfg.questions, 2), 20);
+    }
+
+    #[test]
+    fn zero_full_score_is_incomplete() {
+        let mut cfg = sample();
+        cfg.questions[0].full_score = 0;
+        assert_eq!(student_result(&cfg, "S1").unwrap().percent, None);
+    }
+
+    #[test]
+    fn parts_add_up_to_their_group() {
+        let mut cfg = sample();
+        // Q3 = (a) 4 + (b) 6, weight 4 on the group, parts' own weight ignored
+        cfg.questions.push(question(3, 0, 4.0));
+        cfg.questions.push(Question { parent_id: Some(3), ..question(31, 4, 9.0) });
+        cfg.questions.push(Question { parent_id: Some(3), ..question(32, 6, 9.0) });
+        cfg.scores.set("S1", 31, Some(4.0));
+
+        let tree = Hierarchy::new(&cfg.questions);
+        assert_eq!(tree.leaves(), vec![0, 1, 3, 4]);
+        assert_eq!(tree.full_score(&cfg.questions, 2), 10);
+        assert_eq!(tree.depth(4), 1);
+
+        assert!(!student_result(&cfg, "S1").unwrap().is_complete());
+        cfg.scores.set("S1", 32, Some(1.0));
+        let r = student_result(&cfg, "S1").unwrap();
+        assert_eq!(r.scores[2], Some(5.0));
+        // (1.0 * 1 + 0.5 * 3 + 0.5 * 4) / 8 = 56.25%
+        assert_eq!(r.percent, Some(56.25));
+    }
+
+    #[test]
+    fn half_points_count_and_display() {
+        let mut cfg = sample();
+        cfg.scores.set("S2", 1, Some(4.5));
+        cfg.scores.set("S2", 2, Some(2.5));
+        let r = student_result(&cfg, "S2").unwrap();
+        // (0.45 * 1 + 0.125 * 3) / 4 = 20.625%
+        assert!((r.percent.unwrap() - 20.625).abs() < 1e-4);
+        assert_eq!(table_rows(&cfg, &[r])[0].scores, vec!["4.5", "2.5"]);
+        assert_eq!(format_score(0.1 + 0.2), "0.3");
+        assert_eq!(format_score(7.0), "7");
+    }
+
+    #[test]
+    fn cyclic_parents_are_ignored() {
+        let questions = vec![
+            Question { parent_id: Some(2), ..question(1, 5, 1.0) },
+            Question { parent_id: Some(1), ..question(2, 5, 1.0) },
+            Question { parent_id: Some(9), ..question(3, 5, 1.0) },
+        ];
+        let tree = Hierarchy::new(&questions);
+        assert_eq!(tree.roots().count(), 3);
+    }
+
+    #[test]
+    fn rating_assignment_ignores_order() {
+        let ratings = vec![rating("C", 60), rating("A", 80), rating("B", 70)];
+        let cutoffs = rating_cutoffs(&Config { ratings: ratings.clone(), ..Config::new() }, &[]);
+        assert_eq!(cutoffs, vec![60.0, 80.0, 70.0]);
+        assert_eq!(assign_rating(85.0, &ratings, &cutoffs), Some(1));
+        assert_eq!(assign_rating(70.0, &ratings, &cutoffs), Some(2));
+        assert_eq!(assign_rating(59.5, &ratings, &cutoffs), None);
+
+        let stats = compute_rating_stats(&[85.0, 72.0, 65.0, 10.0], &ratings, &cutoffs);
+        let counts: Vec<usize> = stats.iter().map(|s| s.count).collect();
+        assert_eq!(counts, vec![1, 1, 1]);
+        assert_eq!(stats[0].ratio, 0.25);
+    }
+
+    #[test]
+    fn rounding_drives_display_and_rating() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![question(1, 1000, 1.0)];
+        cfg.students = vec![student("S1")];
+        cfg.scores = vec![score("S1", 1, Some(596.0))].into();
+        cfg.ratings = vec![rating("C", 60)];
+
+        // 59.6: half-up shows and rates 60
+        let rows = table_rows(&cfg, &evaluate(&cfg));
+        assert_eq!((rows[0].final_display.as_str(), rows[0].rating.as_str()), ("60", "C"));
+
+        cfg.rounding = Rounding { mode: RoundingMode::Floor, digits: 0 };
+        let rows = table_rows(&cfg, &evaluate(&cfg));
+        assert_eq!((rows[0].final_display.as_str(), rows[0].rating.as_str()), ("59", ""));
+
+        cfg.rounding = Rounding { mode: RoundingMode::Floor, digits: 1 };
+        assert_eq!(table_rows(&cfg, &evaluate(&cfg))[0].final_display, "59.6");
+
+        let even = Rounding { mode: RoundingMode::HalfEven, digits: 0 };
+        assert_eq!((even.apply(62.5), even.apply(63.5)), (62.0, 64.0));
+        let ceil = Rounding { mode: RoundingMode::Ceil, digits: 0 };
+        assert_eq!((ceil.apply(0.1 + 0.2 + 59.7), ceil.apply(59.01)), (60.0, 60.0));
+    }
+
+    #[test]
+    fn relative_cutoffs_follow_quotas_and_tie_policy() {
+        let mut cfg = Config::new();
+        cfg.rating_mode = RatingMode::Relative;
+        cfg.ratings = vec![
+            Rating { quota: Some(20.0), ..rating("S", 60) },
+            Rating { quota: Some(25.0), ..rating("A", 60) },
+            rating("B", 60),
+        ];
+        let scores = [95.0, 90.0, 90.0, 85.0, 80.0, 75.0, 70.0, 65.0, 60.0, 50.0];
+        let counts = |cfg: &Config| -> Vec<usize> {
+            let cutoffs = rating_cutoffs(cfg, &scores);
+            compute_rating_stats(&scores, &cfg.ratings, &cutoffs).iter().map(|s| s.count).collect()
+        };
+
+        // S may take 2 of 10, but 90 is tied: down keeps S at one student
+        assert_eq!(rating_cutoffs(&cfg, &scores), vec![95.0, 90.0, 60.0]);
+        assert_eq!(counts(&cfg), vec![1, 2, 6]);
+
+        cfg.tie_policy = TiePolicy::Up;
+        assert_eq!(rating_cutoffs(&cfg, &scores), vec![90.0, 80.0, 60.0]);
+        assert_eq!(counts(&cfg), vec![3, 2, 4]);
+
+        // min_score stays a floor under the quota
+        cfg.ratings[0].min_score = 92;
+        assert_eq!(rating_cutoffs(&cfg, &scores)[0], 92.0);
+
+        // a quota too small for the top tie leaves the rating empty
+        cfg.tie_policy = TiePolicy::Down;
+        let cutoffs = rating_cutoffs(&cfg, &[90.0, 90.0]);
+        assert_eq!(cutoffs[0], f32::INFINITY);
+        assert_eq!(assign_rating(90.0, &cfg.ratings, &cutoffs), Some(2));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::models::{
+    Adjustment, CapPolicy, Config, Question, Rating, RatingMode, Rounding, Score, ScoreStatus,
+    ScoreStore, StatusPolicy, Student, TiePolicy,
+};
+
+// Undo / redo history of Config.
+// App records every state the config passes through, whatever page made the edit.
+// Consecutive edits of the same cell (score, id, name, ...) are merged into one step.
+// Steps hold only what changed: the changed run of each list and the small settings,
+// so a score edit costs one cell however large the class is.
+
+const MAX_STEPS: usize = 100;
+// list items (questions, students, score cells, ...) kept over all undo steps
+const MAX_ITEMS: usize = 200_000;
+
+pub struct History {
+    current: Config,
+    // each step turns current into the state before it
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    last_edit: Option<String>,
+}
+
+impl History {
+
+    pub fn new(initial: Config) -> History {
+        History {
+            current: initial,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit: None,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    // Record a new state of the config
+    pub fn record(&mut self, next: &Config) {
+        if content(&self.current) == content(next) {
+            // nothing but save_path changed (save as / load of the same data)
+            self.current.save_path = next.save_path.clone();
+            return;
+        }
+
+        let edit = edit_key(&self.current, next);
+        let merge = edit.is_some() && edit == self.last_edit && self.can_undo();
+        if merge {
+            // the merged step goes back to the state before the first edit of the run
+            if let Some(step) = self.undo.pop() {
+                step.apply(&mut self.current);
+            }
+        }
+        self.undo.push(Step::between(next, &self.current));
+        self.current = next.clone();
+        self.trim();
+        self.redo.clear();
+        self.last_edit = edit;
+    }
+
+    // Step back; returns the config to show
+    pub fn undo(&mut self) -> Option<Config> {
+        let step = self.undo.pop()?;
+        self.redo.push(step.apply(&mut self.current));
+        self.last_edit = None;
+        Some(self.current.clone())
+    }
+
+    // Step forward again; returns the config to show
+    pub fn redo(&mut self) -> Option<Config> {
+        let step = self.redo.pop()?;
+        self.undo.push(step.apply(&mut self.current));
+        self.last_edit = None;
+        Some(self.current.clone())
+    }
+
+    // Drop the oldest steps beyond MAX_STEPS or MAX_ITEMS; the latest step always stays
+    fn trim(&mut self) {
+        let mut items: usize = self.undo.iter().map(Step::size).sum();
+        while self.undo.len() > MAX_STEPS || (self.undo.len() > 1 && items > MAX_ITEMS) {
+            items -= self.undo.remove(0).size();
+        }
+    }
+}
+
+// Replacing items[start..start + len] by `items` gives the other state
+struct Splice<T> {
+    start: usize,
+    len: usize,
+    items: Vec<T>,
+}
+
+impl<T: Clone + PartialEq> Splice<T> {
+
+    // Splice that turns `from` into `to`; the common head and tail are left out
+    fn between(from: &[T], to: &[T]) -> Splice<T> {
+        let head = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+        let tail = from[head..].iter().rev()
+            .zip(to[head..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Splice {
+            start: head,
+            len: from.len() - head - tail,
+            items: to[head..to.len() - tail].to_vec(),
+        }
+    }
+
+    // Apply through `splice` (range, new items) -> removed items; returns the splice back
+    fn apply(self, splice: impl FnOnce(std::ops::Range<usize>, Vec<T>) -> Vec<T>) -> Splice<T> {
+        let len = self.items.len();
+        let removed = splice(self.start..self.start + self.len, self.items);
+        Splice { start: self.start, len, items: removed }
+    }
+}
+
+type Settings = (u32, StatusPolicy, CapPolicy, Rounding, RatingMode, TiePolicy);
+
+fn settings(cfg: &Config) -> Settings {
+    (cfg.schema_version, cfg.status_policy.clone(), cfg.cap_policy, cfg.rounding, cfg.rating_mode, cfg.tie_policy)
+}
+
+// What turns one state of the config into another, save_path aside
+struct Step {
+    questions: Splice<Question>,
+    students: Splice<Student>,
+    scores: Splice<Score>,
+    ratings: Splice<Rating>,
+    adjustments: Splice<Adjustment>,
+    settings: Settings,
+}
+
+impl Step {
+
+    fn between(from: &Config, to: &Config) -> Step {
+        Step {
+            questions: Splice::between(&from.questions, &to.questions),
+            students: Splice::between(&from.students, &to.students),
+            scores: Splice::between(from.scores.as_slice(), to.scores.as_slice()),
+            ratings: Splice::between(&from.ratings, &to.ratings),
+            adjustments: Splice::between(&from.adjustments, &to.adjustments),
+            settings: settings(to),
+        }
+    }
+
+    // Turn the config into the other state; returns the step back
+    fn apply(self, cfg: &mut Config) -> Step {
+        let back = settings(cfg);
+        (cfg.schema_version, cfg.status_policy, cfg.cap_policy, cfg.rounding, cfg.rating_mode, cfg.tie_policy) =
+            self.settings;
+        Step {
+            questions: self.questions.apply(|r, items| cfg.questions.splice(r, items).collect()),
+            students: self.students.apply(|r, items| cfg.students.splice(r, items).collect()),
+            scores: self.scores.apply(|r, items| cfg.scores.splice(r, items)),
+            ratings: self.ratings.apply(|r, items| cfg.ratings.splice(r, items).collect()),
+            adjustments: self.adjustments.apply(|r, items| cfg.adjustments.splice(r, items).collect()),
+            settings: back,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.questions.items.len() + self.students.items.len() + self.scores.items.len()
+            + self.ratings.items.len() + self.adjustments.items.len()
+    }
+}
+
+// Everything in the config except save_path
+type Content<'a> = (u32, &'a [Question], &'a [Student], &'a ScoreStore, &'a [Rating], &'a StatusPolicy, CapPolicy, &'a [Adjustment], Rounding, RatingMode, TiePolicy);
+
+fn content(cfg: &Config) -> Content<'_> {
+    (
+        cfg.schema_version,
+        &cfg.questions,
+        &cfg.students,
+        &cfg.scores,
+        &cfg.ratings,
+        &cfg.status_policy,
+        cfg.cap_policy,
+        &cfg.adjustments,
+        cfg.rounding,
+        cfg.rating_mode,
+        cfg.tie_policy,
+    )
+}
+
+// Identify an edit that touched exactly one cell, e.g. "score:S1:3" or "student:4:name"
+fn edit_key(prev: &Config, next: &Config) -> Option<String> {
+    let questions = prev.questions != next.questions;
+    let students = prev.students != next.students;
+    let scores = prev.scores != next.scores;
+    let ratings = prev.ratings != next.ratings;
+    let status_policy = prev.status_policy != next.status_policy;
+    let cap_policy = prev.cap_policy != next.cap_policy;
+    let adjustments = prev.adjustments != next.adjustments;
+    let rounding = prev.rounding != next.rounding;
+    let rating_mode = prev.rating_mode != next.rating_mode;
+    let tie_policy = prev.tie_policy != next.tie_policy;
+
+    // an edit that touches several parts of the config is a step of its own;
+    // so is a schema upgrade on load
+    let parts = [
+        questions, students, scores, ratings, status_policy, cap_policy, adjustments,
+        rounding, rating_mode, tie_policy,
+    ];
+    if prev.schema_version != next.schema_version || parts.iter().filter(|&&c| c).count() != 1 {
+        return None;
+    }
+
+    if scores {
+        if next.scores.len() < prev.scores.len() {
+            return None;
+        }
+        let mut changed = next.scores.iter().filter(|sc| {
+            prev.scores.get(&sc.student_id, sc.question_id) != Some(*sc)
+        });
+        let cell = changed.next()?;
+        if changed.next().is_some() {
+            return None;
+        }
+        Some(format!("score:{}:{}", cell.student_id, cell.question_id))
+    } else if students {
+        let i = single_change(&prev.students, &next.students)?;
+        let field = if prev.students[i].id != next.students[i].id { "id" } else { "name" };
+        Some(format!("student:{i}:{field}"))
+    } else if questions {
+        let i = single_change(&prev.questions, &next.questions)?;
+        let (a, b) = (&prev.questions[i], &next.questions[i]);
+        let field = if a.id != b.id {
+            "id"
+        } else if a.name != b.name {
+            "name"
+        } else if a.full_score != b.full_score {
+            "full_score"
+        } else if a.weight != b.weight {
+            "weight"
+        } else if a.parent_id != b.parent_id {
+            "parent_id"
+        } else if a.step != b.step {
+            "step"
+        } else if a.bonus != b.bonus {
+            "bonus"
+        } else if a.min_score != b.min_score {
+            "min_score"
+        } else if a.choice != b.choice {
+            "choice"
+        } else if a.rubric != b.rubric {
+            "rubric"
+        } else if a.snippets != b.snippets {
+            "snippets"
+        } else {
+            "comment"
+        };
+        Some(format!("question:{i}:{field}"))
+    } else if ratings {
+        let i = single_change(&prev.ratings, &next.ratings)?;
+        let (a, b) = (&prev.ratings[i], &next.ratings[i]);
+        let field = if a.label != b.label {
+            "label"
+        } else if a.min_score != b.min_score {
+            "min_score"
+        } else {
+            "quota"
+        };
+        Some(format!("rating:{i}:{field}"))
+    } else if cap_policy {
+        Some("cap_policy".to_string())
+    } else if adjustments {
+        let i = single_change(&prev.adjustments, &next.adjustments)?;
+        let (a, b) = (&prev.adjustments[i], &next.adjustments[i]);
+        let field = if a.student_id != b.student_id {
+            "student_id"
+        } else if a.late_penalty != b.late_penalty {
+            "late_penalty"
+        } else if a.deduction != b.deduction {
+            "deduction"
+        } else if a.misconduct != b.misconduct {
+            "misconduct"
+        } else {
+            "reason"
+        };
+        Some(format!("adjustment:{}:{field}", b.student_id))
+    } else if rounding {
+        let field = if prev.rounding.mode != next.rounding.mode { "mode" } else { "digits" };
+        Some(format!("rounding:{field}"))
+    } else if rating_mode {
+        Some("rating_mode".to_string())
+    } else if tie_policy {
+        Some("tie_policy".to_string())
+    } else {
+        let status = ScoreStatus::ALL.into_iter()
+            .find(|&st| prev.status_policy.rule(st) != next.status_policy.rule(st))?;
+        Some(format!("status_policy:{}", status.code()))
+    }
+}
+
+// Index of the only element that differs between two lists of the same length
+fn single_change<T: PartialEq>(prev: &[T], next: &[T]) -> Option<usize> {
+    if prev.len() != next.len() {
+        return None;
+    }
+    let mut diff = prev.iter().zip(next).enumerate().filter(|(_, (a, b))| a != b);
+    let (i, _) = diff.next()?;
+    diff.next().is_none().then_some(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_score(cfg: &Config, sid: &str, qid: u32, v: Option<f32>) -> Config {
+        let mut c = cfg.clone();
+        c.scores.set(sid, qid, v);
+        c
+    }
+
+    #[test]
+    fn keystrokes_in_one_cell_are_one_step() {
+        let base = Config::new();
+        let mut h = History::new(base.clone());
+
+        let c1 = with_score(&base, "S1", 1, Some(1.0));
+        h.record(&c1);
+        let c2 = with_score(&c1, "S1", 1, Some(12.0));
+        h.record(&c2);
+        let c3 = with_score(&c2, "S1", 2, Some(5.0));
+        h.record(&c3);
+
+        assert_eq!(h.undo().unwrap(), c2);
+        assert_eq!(h.undo().unwrap(), base);
+        assert!(!h.can_undo());
+        assert_eq!(h.redo().unwrap(), c2);
+    }
+
+    #[test]
+    fn save_path_is_not_an_edit() {
+        let base = Config::new();
+        let mut h = History::new(base.clone());
+        let mut saved = base.clone();
+        saved.save_path = Some("a.json".to_string());
+        h.record(&saved);
+        assert!(!h.can_undo());
+
+        let edited = with_score(&saved, "S1", 1, Some(3.0));
+        h.record(&edited);
+        assert_eq!(h.undo().unwrap().save_path.as_deref(), Some("a.json"));
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let base = Config::new();
+        let mut h = History::new(base.clone());
+        let mut cleared = base.clone();
+        cleared.students.push(Student { id: "S1".into(), name: String::new() });
+        h.record(&cleared);
+        h.undo();
+        h.record(&with_score(&base, "S1", 1, Some(1.0)));
+        assert!(h.redo().is_none());
+    }
+
+    #[test]
+    fn steps_keep_only_the_change() {
+        let mut base = Config::new();
+        for n in 0..500 {
+            base.scores.set(&format!("S{n}"), 1, Some(1.0));
+        }
+        let mut h = History::new(base.clone());
+        let edited = with_score(&base, "S250", 1, Some(7.0));
+        h.record(&edited);
+        let added = with_score(&edited, "S9", 2, Some(3.0));
+        h.record(&added);
+
+        assert_eq!((h.undo[0].size(), h.undo[1].size()), (1, 0));
+        assert_eq!(h.undo().unwrap(), edited);
+        assert_eq!(h.undo().unwrap(), base);
+        assert_eq!(h.redo().unwrap(), edited);
+        assert_eq!(h.redo().unwrap(), added);
+    }
+
+    #[test]
+    fn settings_changes_are_undo_steps() {
+        let base = Config::new();
+        let mut h = History::new(base.clone());
+        let mut policy = base.clone();
+        policy.status_policy.rule_mut(ScoreStatus::Absent).scoring = crate::models::StatusScoring::Exclude;
+        h.record(&policy);
+        let scored = with_score(&policy, "S1", 1, Some(2.0));
+        h.record(&scored);
+
+        let mut uncapped = scored.clone();
+        uncapped.cap_policy = CapPolicy::Uncapped;
+        h.record(&uncapped);
+
+        let mut late = uncapped.clone();
+        late.edit_adjustment("S1", |a| a.late_penalty = 10.0);
+        h.record(&late);
+        let rescored = with_score(&late, "S1", 1, Some(3.0));
+        h.record(&rescored);
+
+        assert_eq!(h.undo().unwrap(), late);
+        assert_eq!(h.undo().unwrap(), uncapped);
+        assert_eq!(h.undo().unwrap(), scored);
+        assert_eq!(h.undo().unwrap(), policy);
+        assert_eq!(h.undo().unwrap(), base);
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::{Result, bail};
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8};
+use crate::models::Student;
+
+// CSV / TSV roster import.
+// The registrar's files come as UTF-8 or Shift_JIS with arbitrary extra columns,
+// so the table is parsed as-is and the user picks which columns hold id and name.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvTable {
+    pub encoding: &'static str,
+    pub delimiter: u8,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    // keep the current roster, update names of known ids and append new ids
+    Merge,
+    // throw the current roster away
+    Replace,
+}
+
+impl CsvTable {
+
+    // Parse raw file bytes, detecting encoding and delimiter
+    pub fn parse(bytes: &[u8]) -> Result<CsvTable> {
+        let (text, encoding) = decode_text(bytes);
+        if text.trim().is_empty() {
+            bail!("Empty file.");
+        }
+        let delimiter = detect_delimiter(&text);
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let row: Vec<String> = record.iter().map(|c| c.trim().to_string()).collect();
+            if row.iter().all(|c| c.is_empty()) {
+                continue;
+            }
+            rows.push(row);
+        }
+
+        Ok(CsvTable {
+            encoding: encoding.name(),
+            delimiter,
+            rows,
+        })
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.rows.iter().map(|r| r.len()).max().unwrap_or(0)
+    }
+
+    // Column titles: the first row when it is a header, otherwise "列 1", "列 2", ...
+    pub fn column_names(&self, has_header: bool) -> Vec<String> {
+        (0..self.column_count())
+            .map(|i| {
+                let title = has_header
+                    .then(|| self.rows.first().and_then(|r| r.get(i)))
+                    .flatten()
+                    .filter(|t| !t.is_empty());
+                match title {
+                    Some(t) => t.clone(),
+                    None => format!("列 {}", i + 1),
+                }
+            })
+            .collect()
+    }
+
+    pub fn data_rows(&self, has_header: bool) -> &[Vec<String>] {
+        let skip = usize::from(has_header).min(self.rows.len());
+        &self.rows[skip..]
+    }
+
+    // Guess (id column, name column) from the header row
+    pub fn guess_columns(&self) -> (Option<usize>, Option<usize>) {
+        let Some(header) = self.rows.first() else {
+            return (None, None);
+        };
+        let find = |keys: &[&str]| {
+            header.iter().position(|h| {
+                let h = h.to_lowercase();
+                keys.iter().any(|k| h.contains(k))
+            })
+        };
+        let id_col = find(&["学籍番号", "学生番号", "番号", "student_id", "id"]);
+        let name_col = find(&["氏名", "名前", "name"]).filter(|&c| Some(c) != id_col);
+        (id_col, name_col)
+    }
+
+    // Students from the selected columns; rows without an id are skipped
+    pub fn students(
+        &self,
+        has_header: bool,
+        id_col: usize,
+        name_col: Option<usize>,
+    ) -> Vec<Student> {
+        self.data_rows(has_header)
+            .iter()
+            .filter_map(|r| {
+                let id = r.get(id_col).filter(|v| !v.is_empty())?;
+                let name = name_col.and_then(|c| r.get(c)).cloned().unwrap_or_default();
+                Some(Student { id: id.clone(), name })
+            })
+            .collect()
+    }
+
+    // Whether the first row looks like a header (contains a known column title)
+    pub fn looks_like_header(&self) -> bool {
+        let (id_col, name_col) = self.guess_columns();
+        id_col.is_some() || name_col.is_some()
+    }
+}
+
+// Apply imported students to a roster, returning (added, updated)
+pub fn apply_students(
+    roster: &mut Vec<Student>,
+    imported: Vec<Student>,
+    mode: ImportMode,
+) -> (usize, usize) {
+    if mode == ImportMode::Replace {
+        roster.clear();
+    }
+    let (mut added, mut updated) = (0, 0);
+    for s in imported {
+        match roster.iter_mut().find(|r| r.id == s.id) {
+            Some(existing) => {
+                if existing.name != s.name && !s.name.is_empty() {
+                    existing.name = s.name;
+                    updated += 1;
+                }
+            }
+            None => {
+                roster.push(s);
+                added += 1;
+            }
+        }
+    }
+    (added, updated)
+}
+
+// Decode as UTF-8 (BOM stripped) when valid, Shift_JIS otherwise
+fn decode_text(bytes: &[u8]) -> (String, &'static Encoding) {
+    if let Some((enc, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = enc.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), enc);
+    }
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return (s.to_string(), UTF_8);
+    }
+    let (text, _) = SHIFT_JIS.decode_without_bom_handling(bytes);
+    (text.into_owned(), SHIFT_JIS)
+}
+
+// Tab when the first lines contain more tabs than commas
+fn detect_delimiter(text: &str) -> u8 {
+    let head: String = text.lines().take(10).collect();
+    let tabs = head.matches('\t').count();
+    let commas = head.matches(',').count();
+    if tabs > commas { b'\t' } else { b',' }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn student(id: &str, name: &str) -> Student {
+        Student { id: id.to_string(), name: name.to_string() }
+    }
+
+    #[test]
+    fn parses_utf8_csv_with_bom() {
+        let bytes = "\u{feff}学籍番号,氏名,学部\nA001,山田 太郎,工\nA002,佐藤 花子,理\n".as_bytes();
+        let table = CsvTable::parse(bytes).unwrap();
+        assert_eq!(table.encoding, "UTF-8");
+        assert_eq!(table.delimiter, b',');
+        assert_eq!(table.guess_columns(), (Some(0), Some(1)));
+        assert_eq!(
+            table.students(true, 0, Some(1)),
+            vec![student("A001", "山田 太郎"), student("A002", "佐藤 花子")],
+        );
+    }
+
+    #[test]
+    fn parses_shift_jis_tsv() {
+        let (bytes, _, _) = SHIFT_JIS.encode("No\t氏名\t学籍番号\n1\t山田\tA001\n");
+        let table = CsvTable::parse(&bytes).unwrap();
+        assert_eq!(table.encoding, "Shift_JIS");
+        assert_eq!(table.delimiter, b'\t');
+        assert_eq!(table.column_names(true), vec!["No", "氏名", "学籍番号"]);
+        assert_eq!(table.students(true, 2, Some(1)), vec![student("A001", "山田")]);
+    }
+
+    #[test]
+    fn merge_and_replace() {
+        let mut roster = vec![student("A001", "old"), student("A002", "keep")];
+        let imported = vec![student("A001", "new"), student("A003", "added")];
+        assert_eq!(apply_students(&mut roster, imported.clone(), ImportMode::Merge), (1, 1));
+        assert_eq!(roster, vec![student("A001", "new"), student("A002", "keep"), student("A003", "added")]);
+
+        assert_eq!(apply_students(&mut roster, imported.clone(), ImportMode::Replace), (2, 0));
+        assert_eq!(roster, imported);
+    }
+}
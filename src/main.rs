@@ -2,9 +2,22 @@ use dioxus::prelude::*;
 
 mod ui;
 mod models;
+mod grading;
+mod import;
+mod export;
+mod history;
+mod autosave;
+mod backup;
+mod schema;
+mod validation;
+mod report;
+mod analysis;
 
-use ui::{MasterQuestionsPage, MasterStudentsPage, GradingPage, RatingPage};
+use std::time::{Duration, Instant};
+use ui::{MasterQuestionsPage, MasterStudentsPage, GradingPage, RatingPage, ItemAnalysisPage, RecoveryDialog};
 use models::{Config, Page};
+use history::History;
+use autosave::Recovery;
 
 fn main() {
     use dioxus::desktop::tao;
@@ -21,35 +34,112 @@ fn main() {
 #[component]
 fn App() -> Element {
     let mut page = use_signal(|| Page::Grading);
-    let config = use_signal(|| Config::new());
+    let mut config = use_signal(Config::new);
+    let mut history = use_signal(|| History::new(Config::new()));
+    let mut edits = use_signal(|| 0u64);
+    let mut recovery = use_signal(|| None::<Recovery>);
+    let mut recovery_checked = use_signal(|| false);
+
+    // every change of the config becomes an undo step
+    use_effect(move || {
+        let cfg = config.read();
+        history.write().record(&cfg);
+        *edits.write() += 1;
+    });
+
+    // offer the autosave of the last session
+    use_future(move || async move {
+        recovery.set(autosave::find_recovery().await);
+        recovery_checked.set(true);
+    });
+
+    // autosave: after a few idle seconds, and at least every minute while editing
+    use_future(move || async move {
+        let mut seen = 0u64;
+        let mut changed_at = Instant::now();
+        let mut dirty_since: Option<Instant> = None;
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            // keep the old recovery file until the user has answered the dialog
+            if !*recovery_checked.peek() || recovery.peek().is_some() {
+                continue;
+            }
+            let gen = *edits.peek();
+            if gen != seen {
+                seen = gen;
+                changed_at = Instant::now();
+                dirty_since.get_or_insert(changed_at);
+            }
+            let Some(since) = dirty_since else {
+                continue;
+            };
+            if changed_at.elapsed() < Duration::from_secs(autosave::IDLE_SECS)
+                && since.elapsed() < Duration::from_secs(autosave::INTERVAL_SECS)
+            {
+                continue;
+            }
+            dirty_since = None;
+            let cfg = config.peek().clone();
+            if cfg.questions.is_empty() && cfg.students.is_empty() {
+                continue;
+            }
+            let _ = autosave::write_recovery(&cfg).await;
+        }
+    });
+
+    let onkeydown = move |e: KeyboardEvent| {
+        let mods = e.modifiers();
+        if !(mods.contains(Modifiers::CONTROL) || mods.contains(Modifiers::META)) {
+            return;
+        }
+        let restored = match e.code() {
+            Code::KeyZ if mods.contains(Modifiers::SHIFT) => history.write().redo(),
+            Code::KeyZ => history.write().undo(),
+            Code::KeyY => history.write().redo(),
+            _ => return,
+        };
+        e.prevent_default();
+        if let Some(cfg) = restored {
+            config.set(cfg);
+        }
+    };
 
     rsx! {
         document::Stylesheet { href: asset!("assets/tailwind.css") }
-        match *page.read() {
-            Page::MasterQuestions => rsx! {
-                MasterQuestionsPage {
-                    on_nav: move |p| page.set(p),
-                    config,
-                }
-            },
-            Page::MasterStudents => rsx! {
-                MasterStudentsPage {
-                    on_nav: move |p| page.set(p),
-                    config,
-                }
-            },
-            Page::Grading => rsx! {
-                GradingPage { 
-                    on_nav: move |p| page.set(p),
-                    config,                    
-                }
-            },
-            Page::Rating => rsx! {
-                RatingPage {
-                    on_nav: move |p| page.set(p),
-                    config,
-                }
-            },
+        div { onkeydown,
+            RecoveryDialog { recovery, config }
+            match *page.read() {
+                Page::MasterQuestions => rsx! {
+                    MasterQuestionsPage {
+                        on_nav: move |p| page.set(p),
+                        config,
+                    }
+                },
+                Page::MasterStudents => rsx! {
+                    MasterStudentsPage {
+                        on_nav: move |p| page.set(p),
+                        config,
+                    }
+                },
+                Page::Grading => rsx! {
+                    GradingPage { 
+                        on_nav: move |p| page.set(p),
+                        config,                    
+                    }
+                },
+                Page::Rating => rsx! {
+                    RatingPage {
+                        on_nav: move |p| page.set(p),
+                        config,
+                    }
+                },
+                Page::Analysis => rsx! {
+                    ItemAnalysisPage {
+                        on_nav: move |p| page.set(p),
+                        config,
+                    }
+                },
+            }
         }
     }
 }
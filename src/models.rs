@@ -1,16 +1,117 @@
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use anyhow::{Result, Context};
 use dioxus::prelude::*;
+use crate::{autosave, backup, schema, validation};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Store)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Store)]
 pub struct Config {
+    pub schema_version: u32,
     pub save_path: Option<String>,
     pub questions: Vec<Question>,
     pub students: Vec<Student>,
-    pub scores: Vec<Score>,
+    pub scores: ScoreStore,
     pub ratings: Vec<Rating>,
+    // how 欠席 / 免除 / 未提出 cells are counted
+    #[serde(default)]
+    pub status_policy: StatusPolicy,
+    // whether bonus points may push the final percentage above 100
+    #[serde(default)]
+    pub cap_policy: CapPolicy,
+    // per-student penalties, applied after the weighted percentage
+    #[serde(default)]
+    pub adjustments: Vec<Adjustment>,
+    // rounding of the final score; rating, display and export all use it
+    #[serde(default)]
+    pub rounding: Rounding,
+    // absolute cut-offs, or cut-offs derived from the distribution (quotas)
+    #[serde(default)]
+    pub rating_mode: RatingMode,
+    // who gets the higher rating when a tie straddles a quota
+    #[serde(default)]
+    pub tie_policy: TiePolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapPolicy {
+    #[default]
+    Cap100,
+    Uncapped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    // 四捨五入
+    #[default]
+    HalfUp,
+    // 偶数丸め (banker's rounding)
+    HalfEven,
+    // 切り捨て
+    Floor,
+    // 切り上げ
+    Ceil,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RatingMode {
+    // 絶対評価: min_score is the cut-off
+    #[default]
+    Absolute,
+    // 相対評価: quotas cap the share of students, min_score stays a floor
+    Relative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TiePolicy {
+    // the tied group goes to the lower rating; a quota is never exceeded
+    #[default]
+    Down,
+    // the tied group gets the higher rating; a quota may be exceeded
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Rounding {
+    pub mode: RoundingMode,
+    // decimal places of the final score (0 = integer)
+    pub digits: u8,
+}
+
+impl Rounding {
+
+    pub const MAX_DIGITS: u8 = 2;
+
+    // Round a percentage; float noise (59.999998) is removed first by working
+    // in integer units of 0.0001
+    pub fn apply(self, value: f32) -> f32 {
+        let digits = self.digits.min(Self::MAX_DIGITS) as u32;
+        let n = ((value as f64) * 1e4).round() as i64;
+        let unit = 10i64.pow(4 - digits);
+        let (q, r) = (n.div_euclid(unit), n.rem_euclid(unit));
+        let q = match self.mode {
+            RoundingMode::Floor => q,
+            RoundingMode::Ceil => if r > 0 { q + 1 } else { q },
+            RoundingMode::HalfUp => if 2 * r >= unit { q + 1 } else { q },
+            RoundingMode::HalfEven => match (2 * r).cmp(&unit) {
+                std::cmp::Ordering::Less => q,
+                std::cmp::Ordering::Greater => q + 1,
+                std::cmp::Ordering::Equal => q + q.rem_euclid(2),
+            },
+        };
+        (q as f64 / 10f64.powi(digits as i32)) as f32
+    }
+
+    // Rounded value with exactly `digits` decimal places
+    pub fn format(self, value: f32) -> String {
+        let digits = self.digits.min(Self::MAX_DIGITS) as usize;
+        format!("{:.*}", digits, self.apply(value) + 0.0)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Store)]
@@ -20,6 +121,81 @@ pub struct Question {
     pub full_score: u32,
     pub weight: f32,
     pub comment: String,
+    // parent question of a part (e.g. Q3 for "Q3 (a)"); None for top-level questions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u32>,
+    // smallest scoring unit, e.g. 0.5 for half points
+    #[serde(default = "default_step")]
+    pub step: f32,
+    // lowest score of the question; below 0 for negative marking
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub min_score: f32,
+    // for a question with parts: only N of the parts count
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub choice: Option<Choice>,
+    // extra credit: adds to the total without counting toward the denominator
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bonus: bool,
+    // structured rubric; when present the score is derived from ticked items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rubric: Vec<RubricItem>,
+    // reusable feedback remarks, inserted with Alt+1..9 while grading
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<Snippet>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Snippet {
+    // unique within the question
+    pub id: u32,
+    pub text: String,
+    // points taken off when inserted (0 = none)
+    #[serde(default)]
+    pub deduction: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RubricItem {
+    // unique within the question
+    pub id: u32,
+    pub criterion: String,
+    pub points: f32,
+    // subtracted instead of added when ticked
+    #[serde(default)]
+    pub deduction: bool,
+}
+
+// "answer any N" rule of a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Choice {
+    pub rule: ChoiceRule,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChoiceRule {
+    // the N parts with the best rate (score / full)
+    Best,
+    // the first N answered parts, in question order
+    Chosen,
+}
+
+// Penalties of one student; applied in the order described at the top of grading.rs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Adjustment {
+    pub student_id: String,
+    // late submission: percent taken off the score (10 = -10%)
+    #[serde(default)]
+    pub late_penalty: f32,
+    // fixed deduction in percentage points
+    #[serde(default)]
+    pub deduction: f32,
+    // academic misconduct: final score 0
+    #[serde(default)]
+    pub misconduct: bool,
+    #[serde(default)]
+    pub reason: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Store)]
@@ -28,17 +204,92 @@ pub struct Student {
     pub name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Store)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Store)]
 pub struct Score {
     pub student_id: String,
     pub question_id: u32,
-    pub score: Option<u32>,
+    #[serde(serialize_with = "serialize_score")]
+    pub score: Option<f32>,
+    // status code recorded instead of a value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ScoreStatus>,
+    // rubric item ids the score was derived from (None = entered by hand)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticked: Option<Vec<u32>>,
+    // private grader note, never shown to the student
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+    // feedback for the student, printed in the individual report
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub feedback: String,
+    // snippets inserted into this cell, as they were applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<UsedSnippet>,
+}
+
+// A snippet as inserted into a cell. Undoing it uses this record, so editing
+// the snippet afterwards does not change what is given back.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsedSnippet {
+    pub id: u32,
+    // line added to the feedback
+    pub text: String,
+    // points actually taken off (after the min_score floor; 0 for a blank cell)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub deduction: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreStatus {
+    Absent,
+    Exempt,
+    NotSubmitted,
+}
+
+// How a status code enters the weighted total
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusScoring {
+    // counts as 0 points
+    Zero,
+    // the question is dropped and the other questions re-weighted
+    Exclude,
+    // same as 未入力
+    Blank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatusRule {
+    pub scoring: StatusScoring,
+    // counts towards the 入力済み counter
+    pub complete: bool,
+    // included in the rating statistics and histogram
+    pub in_stats: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusPolicy {
+    pub absent: StatusRule,
+    pub exempt: StatusRule,
+    pub not_submitted: StatusRule,
+}
+
+// Scores indexed by (student_id, question_id).
+// Serialized as a plain `scores` array, same as the file format.
+#[derive(Debug, Clone, Default)]
+pub struct ScoreStore {
+    entries: Vec<Score>,
+    index: HashMap<String, HashMap<u32, usize>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Store)]
 pub struct Rating {
     pub label: String,
     pub min_score: u32,
+    // relative mode: at most this percentage of the students (None = no cap)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<f32>,
 }
 
 // ----------- for UI display -----------
@@ -48,7 +299,12 @@ pub struct TableRow {
     pub student_id: String,
     pub student_name: String,
     pub scores: Vec<String>,
+    // cells left out by a choice rule, shown greyed out
+    pub dropped: Vec<bool>,
+    // summary of the student's adjustments
+    pub adjustment: String,
     pub final_display: String,
+    pub rating: String,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -57,6 +313,7 @@ pub enum Page {
     MasterStudents,
     Grading,
     Rating,
+    Analysis,
 }
 
 // ----------- implementation --------------
@@ -66,11 +323,18 @@ impl Config {
     // Create blunk config
     pub fn new() -> Config {
         Config {
+            schema_version: schema::CURRENT_VERSION,
             save_path: None,
             questions: Vec::new(),
             students: Vec::new(),
-            scores: Vec::new(),
+            scores: ScoreStore::new(),
             ratings: Vec::new(),
+            status_policy: StatusPolicy::default(),
+            cap_policy: CapPolicy::default(),
+            adjustments: Vec::new(),
+            rounding: Rounding::default(),
+            rating_mode: RatingMode::default(),
+            tie_policy: TiePolicy::default(),
         }
     }
 
@@ -89,12 +353,147 @@ impl Config {
         }
         let json = serde_json::to_string_pretty(self)
             .context("Failed to serialize Config to JSON")?;
-        fs::write(path, json)
+        backup::rotate(path, backup::KEEP)
             .await
-            .with_context(|| format!("Failed to write file: {:?}", path))?;
+            .context("Failed to back up the previous version")?;
+        backup::write_atomic(path, json.as_bytes()).await?;
         Ok(())
     }
 
+    // Change a student id and move its scores along; returns the number of moved scores.
+    // Scores stay put while another roster row still uses the old id.
+    pub fn rename_student(&mut self, idx: usize, new_id: &str) -> usize {
+        let Some(s) = self.students.get_mut(idx) else {
+            return 0;
+        };
+        let old = std::mem::replace(&mut s.id, new_id.to_string());
+        if old == new_id || self.students.iter().any(|s| s.id == old) {
+            return 0;
+        }
+        self.move_adjustment(&old, new_id);
+        self.scores.rename_student(&old, new_id)
+    }
+
+    // Remove a student row and merge its scores into an existing student
+    pub fn merge_student(&mut self, idx: usize, into_id: &str) -> usize {
+        if idx >= self.students.len() {
+            return 0;
+        }
+        let old = self.students.remove(idx).id;
+        if old == into_id || self.students.iter().any(|s| s.id == old) {
+            return 0;
+        }
+        self.move_adjustment(&old, into_id);
+        self.scores.rename_student(&old, into_id)
+    }
+
+    pub fn adjustment(&self, student_id: &str) -> Option<&Adjustment> {
+        self.adjustments.iter().find(|a| a.student_id == student_id)
+    }
+
+    // Edit the adjustment of a student; entries left without any penalty are dropped
+    pub fn edit_adjustment<F: FnOnce(&mut Adjustment)>(&mut self, student_id: &str, f: F) {
+        let i = match self.adjustments.iter().position(|a| a.student_id == student_id) {
+            Some(i) => i,
+            None => {
+                self.adjustments.push(Adjustment { student_id: student_id.to_string(), ..Default::default() });
+                self.adjustments.len() - 1
+            }
+        };
+        f(&mut self.adjustments[i]);
+        if self.adjustments[i].is_empty() {
+            self.adjustments.remove(i);
+        }
+    }
+
+    // The target's own adjustment wins over the one moved onto it
+    fn move_adjustment(&mut self, from: &str, to: &str) {
+        if self.adjustment(to).is_some() {
+            self.adjustments.retain(|a| a.student_id != from);
+        } else if let Some(a) = self.adjustments.iter_mut().find(|a| a.student_id == from) {
+            a.student_id = to.to_string();
+        }
+    }
+
+    // Change a question id and move its scores along, as rename_student
+    pub fn rename_question(&mut self, idx: usize, new_id: u32) -> usize {
+        let Some(q) = self.questions.get_mut(idx) else {
+            return 0;
+        };
+        let old = std::mem::replace(&mut q.id, new_id);
+        if old == new_id || self.questions.iter().any(|q| q.id == old) {
+            return 0;
+        }
+        self.reparent(old, Some(new_id));
+        self.scores.rename_question(old, new_id)
+    }
+
+    // Remove a question row and merge its scores into an existing question
+    pub fn merge_question(&mut self, idx: usize, into_id: u32) -> usize {
+        if idx >= self.questions.len() {
+            return 0;
+        }
+        let old = self.questions.remove(idx).id;
+        if old == into_id || self.questions.iter().any(|q| q.id == old) {
+            return 0;
+        }
+        self.reparent(old, Some(into_id));
+        self.scores.rename_question(old, into_id)
+    }
+
+    // Remove a question row; its parts move up to the removed question's parent
+    pub fn remove_question(&mut self, idx: usize) {
+        if idx >= self.questions.len() {
+            return;
+        }
+        let removed = self.questions.remove(idx);
+        if !self.questions.iter().any(|q| q.id == removed.id) {
+            self.reparent(removed.id, removed.parent_id);
+        }
+    }
+
+    // Point the parts of one question at another; a part never becomes its own parent
+    fn reparent(&mut self, from: u32, to: Option<u32>) {
+        for q in self.questions.iter_mut().filter(|q| q.parent_id == Some(from)) {
+            q.parent_id = to.filter(|&p| p != q.id);
+        }
+    }
+
+    // Parse a config file, upgrading older schema versions.
+    // Fails with a readable message (syntax errors, newer schema, type mismatch).
+    pub fn from_json(txt: &str) -> std::result::Result<Config, String> {
+        let mut value: serde_json::Value = serde_json::from_str(txt)
+            .map_err(|e| format_json_error(txt, e))?;
+        let from = schema::migrate(&mut value)?;
+        if from == schema::CURRENT_VERSION {
+            // parse the text itself so that errors point at a line
+            return serde_json::from_str::<Config>(txt).map_err(|e| format_json_error(txt, e));
+        }
+        serde_json::from_value::<Config>(value)
+            .map_err(|e| format!("JSON structure/type mismatch (file schema {from}): {e}"))
+    }
+
+    // Replace the config by a backup of the current file, keeping save_path
+    pub fn restore_backup(mut config: Signal<Config>, backup_path: PathBuf, mut msg: Signal<String>) {
+        spawn(async move {
+            let txt = match fs::read_to_string(&backup_path).await {
+                Ok(s) => s,
+                Err(e) => {
+                    msg.set(format!("Failed to read backup: {e}"));
+                    return;
+                }
+            };
+            match Config::from_json(&txt) {
+                Ok(mut cfg) => {
+                    cfg.save_path = config.read().save_path.clone();
+                    config.set(cfg);
+                    msg.set(format!("Restored: {:?}", backup_path));
+                }
+                Err(e) => msg.set(e),
+            }
+        });
+    }
+
     // Save config to a stored filepath
     pub fn save(config: Signal<Config>, mut msg: Signal<String>) {
         let cfg = config().clone();
@@ -107,6 +506,7 @@ impl Config {
             msg.set(format!("Saving to {:?} ...", path));
             match cfg.save_to_filepath(&path).await {
                 Ok(()) => {
+                    autosave::clear_recovery(&cfg).await;
                     msg.set(format!("Saved: {:?}", path));
                 }
                 Err(e) => {
@@ -135,6 +535,7 @@ impl Config {
 
             match cfg_snapshot.save_to_filepath(&path).await {
                 Ok(()) => {
+                    autosave::clear_recovery(&cfg_snapshot).await;
                     let mut c = config.write();
                     c.save_path = Some(path.to_string_lossy().to_string());
                     msg.set(format!("Saved: {:?}", path));
@@ -146,8 +547,8 @@ impl Config {
         });
     }
 
-    // Load config from a selected filepath
-    pub fn load(mut config: Signal<Config>, mut msg: Signal<String>) {
+    // Load config from a selected filepath; `check_open` is raised when it has integrity issues
+    pub fn load(mut config: Signal<Config>, mut msg: Signal<String>, mut check_open: Signal<bool>) {
         spawn(async move {
             let handle = rfd::AsyncFileDialog::new()
                 .add_filter("JSON", &["json"])
@@ -175,14 +576,20 @@ impl Config {
                 return;
             }
 
-            match serde_json::from_str::<Config>(&txt) {
+            match Config::from_json(&txt) {
                 Ok(mut cfg) => {
                     cfg.save_path = Some(path.to_string_lossy().to_string());
+                    let issues = validation::validate(&cfg).len();
                     config.set(cfg);
-                    msg.set("Loaded.".to_string());
+                    if issues > 0 {
+                        msg.set(format!("Loaded with {issues} issue(s)."));
+                        check_open.set(true);
+                    } else {
+                        msg.set("Loaded.".to_string());
+                    }
                 }
                 Err(e) => {
-                    msg.set(format_json_error(&txt, e));
+                    msg.set(e);
                 }
             }
         });
@@ -190,6 +597,448 @@ impl Config {
 
 }
 
+impl Default for Question {
+    fn default() -> Question {
+        Question {
+            id: 0,
+            name: String::new(),
+            full_score: 0,
+            weight: 1.0,
+            comment: String::new(),
+            parent_id: None,
+            step: default_step(),
+            min_score: 0.0,
+            choice: None,
+            bonus: false,
+            rubric: Vec::new(),
+            snippets: Vec::new(),
+        }
+    }
+}
+
+fn default_step() -> f32 {
+    1.0
+}
+
+fn is_zero(v: &f32) -> bool {
+    *v == 0.0
+}
+
+impl Question {
+
+    // Whether a score can be entered: not below min_score and a multiple of step.
+    // Scores above full_score are allowed (extra credit) but flagged by over_full.
+    pub fn accepts(&self, score: f32) -> bool {
+        score >= self.min_score.min(0.0) && self.on_step(score)
+    }
+
+    pub fn over_full(&self, score: f32) -> bool {
+        score > self.full_score as f32
+    }
+
+    pub fn on_step(&self, score: f32) -> bool {
+        if self.step <= 0.0 {
+            return true;
+        }
+        let n = score / self.step;
+        (n - n.round()).abs() < 1e-4
+    }
+
+    // Nearest multiple of step
+    pub fn snap(&self, score: f32) -> f32 {
+        if self.step <= 0.0 {
+            return score;
+        }
+        (score / self.step).round() * self.step
+    }
+
+    // Score for a set of ticked rubric items, within min_score..=full_score.
+    // A rubric made only of deductions starts from full_score.
+    pub fn rubric_score(&self, ticked: &[u32]) -> f32 {
+        let base = if self.rubric.iter().any(|r| !r.deduction) { 0.0 } else { self.full_score as f32 };
+        let total = self.rubric.iter()
+            .filter(|r| ticked.contains(&r.id))
+            .fold(base, |acc, r| if r.deduction { acc - r.points } else { acc + r.points });
+        total.min(self.full_score as f32).max(self.min_score.min(0.0))
+    }
+}
+
+impl Score {
+
+    // Has a value or a status code
+    pub fn is_filled(&self) -> bool {
+        self.score.is_some() || self.status.is_some()
+    }
+}
+
+impl Adjustment {
+
+    pub fn is_empty(&self) -> bool {
+        self.late_penalty == 0.0 && self.deduction == 0.0 && !self.misconduct && self.reason.is_empty()
+    }
+
+    // Short text for the grade matrix, e.g. "遅延-10% / -5"
+    pub fn summary(&self) -> String {
+        if self.misconduct {
+            return "不正".to_string();
+        }
+        let mut parts = Vec::new();
+        if self.late_penalty != 0.0 {
+            parts.push(format!("遅延-{}%", self.late_penalty));
+        }
+        if self.deduction != 0.0 {
+            parts.push(format!("-{}", self.deduction));
+        }
+        parts.join(" / ")
+    }
+}
+
+impl ScoreStatus {
+
+    pub const ALL: [ScoreStatus; 3] = [ScoreStatus::Absent, ScoreStatus::Exempt, ScoreStatus::NotSubmitted];
+
+    // Short code shown in score cells and exports
+    pub fn code(self) -> &'static str {
+        match self {
+            ScoreStatus::Absent => "欠",
+            ScoreStatus::Exempt => "免",
+            ScoreStatus::NotSubmitted => "未",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScoreStatus::Absent => "欠席",
+            ScoreStatus::Exempt => "免除",
+            ScoreStatus::NotSubmitted => "未提出",
+        }
+    }
+}
+
+impl StatusPolicy {
+
+    pub fn rule(&self, status: ScoreStatus) -> &StatusRule {
+        match status {
+            ScoreStatus::Absent => &self.absent,
+            ScoreStatus::Exempt => &self.exempt,
+            ScoreStatus::NotSubmitted => &self.not_submitted,
+        }
+    }
+
+    pub fn rule_mut(&mut self, status: ScoreStatus) -> &mut StatusRule {
+        match status {
+            ScoreStatus::Absent => &mut self.absent,
+            ScoreStatus::Exempt => &mut self.exempt,
+            ScoreStatus::NotSubmitted => &mut self.not_submitted,
+        }
+    }
+}
+
+impl Default for StatusPolicy {
+    fn default() -> StatusPolicy {
+        StatusPolicy {
+            absent: StatusRule { scoring: StatusScoring::Zero, complete: true, in_stats: true },
+            exempt: StatusRule { scoring: StatusScoring::Exclude, complete: true, in_stats: true },
+            not_submitted: StatusRule { scoring: StatusScoring::Zero, complete: true, in_stats: false },
+        }
+    }
+}
+
+impl ScoreStore {
+
+    pub fn new() -> ScoreStore {
+        ScoreStore::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Score> {
+        self.entries.iter()
+    }
+
+    pub fn as_slice(&self) -> &[Score] {
+        &self.entries
+    }
+
+    // Replace a run of cells in stored order, for undo / redo; returns the replaced cells
+    pub fn splice(&mut self, range: std::ops::Range<usize>, cells: Vec<Score>) -> Vec<Score> {
+        let removed = self.entries.splice(range, cells).collect();
+        self.reindex();
+        removed
+    }
+
+    pub fn get(&self, student_id: &str, question_id: u32) -> Option<&Score> {
+        self.index
+            .get(student_id)
+            .and_then(|m| m.get(&question_id))
+            .map(|&i| &self.entries[i])
+    }
+
+    // Score value of a cell (None = 未入力)
+    pub fn value(&self, student_id: &str, question_id: u32) -> Option<f32> {
+        self.get(student_id, question_id).and_then(|sc| sc.score)
+    }
+
+    pub fn status(&self, student_id: &str, question_id: u32) -> Option<ScoreStatus> {
+        self.get(student_id, question_id).and_then(|sc| sc.status)
+    }
+
+    pub fn ticked(&self, student_id: &str, question_id: u32) -> Option<&[u32]> {
+        self.get(student_id, question_id).and_then(|sc| sc.ticked.as_deref())
+    }
+
+    // Insert or overwrite the score of a cell; clears its status and ticked items.
+    // Used snippets are forgotten too: their deductions are not part of the new value.
+    pub fn set(&mut self, student_id: &str, question_id: u32, score: Option<f32>) {
+        let cell = self.cell_mut(student_id, question_id);
+        cell.score = score;
+        cell.status = None;
+        cell.ticked = None;
+        cell.snippets.clear();
+    }
+
+    // Change only the value of a cell; status, ticked items and used snippets stay.
+    // For corrections such as the integrity fixes, not for entering a new score.
+    pub fn set_value(&mut self, student_id: &str, question_id: u32, score: Option<f32>) {
+        self.cell_mut(student_id, question_id).score = score;
+    }
+
+    // Record a status code instead of a value
+    pub fn set_status(&mut self, student_id: &str, question_id: u32, status: Option<ScoreStatus>) {
+        let cell = self.cell_mut(student_id, question_id);
+        if status.is_some() {
+            cell.score = None;
+            cell.ticked = None;
+        }
+        cell.status = status;
+    }
+
+    // Score a cell from ticked rubric items of the question
+    pub fn set_ticked(&mut self, student_id: &str, question: &Question, ticked: Vec<u32>) {
+        let cell = self.cell_mut(student_id, question.id);
+        cell.score = Some(question.rubric_score(&ticked));
+        cell.status = None;
+        cell.ticked = Some(ticked);
+    }
+
+    // Re-derive the rubric-scored cells of a question after its rubric changed
+    pub fn rescore(&mut self, question: &Question) -> usize {
+        let mut count = 0;
+        for sc in self.entries.iter_mut().filter(|sc| sc.question_id == question.id) {
+            if let Some(ticked) = &mut sc.ticked {
+                ticked.retain(|id| question.rubric.iter().any(|r| r.id == *id));
+                sc.score = Some(question.rubric_score(ticked));
+                count += 1;
+            }
+        }
+        count
+    }
+
+    pub fn set_note(&mut self, student_id: &str, question_id: u32, note: String) {
+        self.cell_mut(student_id, question_id).note = note;
+    }
+
+    pub fn set_feedback(&mut self, student_id: &str, question_id: u32, feedback: String) {
+        self.cell_mut(student_id, question_id).feedback = feedback;
+    }
+
+    // Append a snippet to the feedback and apply its deduction
+    // (only to an entered score; a blank cell stays blank). Returns false if already used.
+    pub fn use_snippet(&mut self, student_id: &str, question: &Question, snippet_id: u32) -> bool {
+        let Some(snippet) = question.snippets.iter().find(|sn| sn.id == snippet_id) else {
+            return false;
+        };
+        let cell = self.cell_mut(student_id, question.id);
+        if cell.snippets.iter().any(|u| u.id == snippet_id) {
+            return false;
+        }
+        if !cell.feedback.is_empty() && !cell.feedback.ends_with('\n') {
+            cell.feedback.push('\n');
+        }
+        cell.feedback.push_str(&snippet.text);
+        // rubric questions keep their derived score
+        let mut deduction = 0.0;
+        if snippet.deduction > 0.0 && question.rubric.is_empty() {
+            if let Some(v) = cell.score {
+                let after = (v - snippet.deduction).max(question.min_score.min(0.0));
+                deduction = (v - after).max(0.0);
+                cell.score = Some(after);
+            }
+        }
+        cell.snippets.push(UsedSnippet { id: snippet_id, text: snippet.text.clone(), deduction });
+        true
+    }
+
+    // Undo use_snippet: drop its line from the feedback and give the points back,
+    // both as recorded when it was used
+    pub fn unuse_snippet(&mut self, student_id: &str, question_id: u32, snippet_id: u32) -> bool {
+        let Some(cell) = self.get(student_id, question_id) else {
+            return false;
+        };
+        let Some(pos) = cell.snippets.iter().position(|u| u.id == snippet_id) else {
+            return false;
+        };
+        let cell = self.cell_mut(student_id, question_id);
+        let used = cell.snippets.remove(pos);
+        if let Some(pos) = cell.feedback.lines().position(|l| l == used.text) {
+            cell.feedback = cell.feedback.lines()
+                .enumerate()
+                .filter(|(i, _)| *i != pos)
+                .map(|(_, l)| l)
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        if let Some(v) = cell.score {
+            cell.score = Some(v + used.deduction);
+        }
+        true
+    }
+
+    // Forget a deleted snippet in every cell of the question, so its id can be reused
+    pub fn purge_snippet(&mut self, question_id: u32, snippet_id: u32) {
+        for sc in self.entries.iter_mut().filter(|sc| sc.question_id == question_id) {
+            sc.snippets.retain(|u| u.id != snippet_id);
+        }
+    }
+
+    // How many cells of a question use each snippet
+    pub fn snippet_usage(&self, question_id: u32) -> HashMap<u32, usize> {
+        let mut usage = HashMap::new();
+        for sc in self.entries.iter().filter(|sc| sc.question_id == question_id) {
+            for u in sc.snippets.iter() {
+                *usage.entry(u.id).or_default() += 1;
+            }
+        }
+        usage
+    }
+
+    fn cell_mut(&mut self, student_id: &str, question_id: u32) -> &mut Score {
+        let i = match self.index.get(student_id).and_then(|m| m.get(&question_id)) {
+            Some(&i) => i,
+            None => {
+                self.push(Score {
+                    student_id: student_id.to_string(),
+                    question_id,
+                    ..Default::default()
+                });
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[i]
+    }
+
+    // Keep only the scores matching the predicate
+    pub fn retain<F: FnMut(&Score) -> bool>(&mut self, f: F) {
+        self.entries.retain(f);
+        self.reindex();
+    }
+
+    // Move the scores of one student id to another.
+    // Cells the target already has a value for are kept; returns the number of moved cells.
+    pub fn rename_student(&mut self, from: &str, to: &str) -> usize {
+        self.rename(|sc| sc.student_id == from, |sc| sc.student_id = to.to_string())
+    }
+
+    // Move the scores of one question id to another, as rename_student
+    pub fn rename_question(&mut self, from: u32, to: u32) -> usize {
+        self.rename(|sc| sc.question_id == from, |sc| sc.question_id = to)
+    }
+
+    fn rename<P, F>(&mut self, matches: P, relabel: F) -> usize
+    where
+        P: Fn(&Score) -> bool,
+        F: Fn(&mut Score),
+    {
+        let (moved, rest): (Vec<Score>, Vec<Score>) =
+            std::mem::take(&mut self.entries).into_iter().partition(|sc| matches(sc));
+        self.entries = rest;
+        self.reindex();
+
+        let mut count = 0;
+        for mut sc in moved {
+            relabel(&mut sc);
+            let taken = self.get(&sc.student_id, sc.question_id).is_some_and(|t| t.is_filled());
+            if !taken {
+                self.push(sc);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn push(&mut self, score: Score) {
+        use std::collections::hash_map::Entry;
+        let cells = self.index.entry(score.student_id.clone()).or_default();
+        match cells.entry(score.question_id) {
+            // duplicated cell in a file: the last one wins
+            Entry::Occupied(e) => self.entries[*e.get()] = score,
+            Entry::Vacant(e) => {
+                e.insert(self.entries.len());
+                self.entries.push(score);
+            }
+        }
+    }
+
+    fn reindex(&mut self) {
+        let entries = std::mem::take(&mut self.entries);
+        self.index.clear();
+        for sc in entries {
+            self.push(sc);
+        }
+    }
+}
+
+impl PartialEq for ScoreStore {
+    fn eq(&self, other: &ScoreStore) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl From<Vec<Score>> for ScoreStore {
+    fn from(scores: Vec<Score>) -> ScoreStore {
+        let mut store = ScoreStore::new();
+        for sc in scores {
+            store.push(sc);
+        }
+        store
+    }
+}
+
+impl<'a> IntoIterator for &'a ScoreStore {
+    type Item = &'a Score;
+    type IntoIter = std::slice::Iter<'a, Score>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl Serialize for ScoreStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.entries.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScoreStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Vec::<Score>::deserialize(deserializer).map(ScoreStore::from)
+    }
+}
+
+// Whole scores are written as integers, as before decimal scores existed
+fn serialize_score<S: Serializer>(score: &Option<f32>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    match score {
+        Some(v) if v.fract() == 0.0 && *v >= 0.0 => serializer.serialize_u64(*v as u64),
+        Some(v) => serializer.serialize_f32(*v),
+        None => serializer.serialize_none(),
+    }
+}
+
 fn format_json_error(src: &str, e: serde_json::Error) -> String {
     let (line, col) = (e.line(), e.column());
 
@@ -225,4 +1074,185 @@ fn format_json_error(src: &str, e: serde_json::Error) -> String {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_store_roundtrips_as_array() {
+        let json = r#"[
+            {"student_id":"S1","question_id":1,"score":3},
+            {"student_id":"S2","question_id":1,"score":null},
+            {"student_id":"S1","question_id":1,"score":5}
+        ]"#;
+        let store: ScoreStore = serde_json::from_str(json).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.value("S1", 1), Some(5.0));
+        assert_eq!(store.value("S2", 1), None);
+
+        let back = serde_json::to_value(&store).unwrap();
+        assert_eq!(back[0]["score"], 5);
+        let half = serde_json::to_string(&Score { student_id: "S1".into(), question_id: 1, score: Some(2.5), ..Default::default() }).unwrap();
+        assert!(half.contains(r#""score":2.5"#));
+        assert_eq!(back[1]["student_id"], "S2");
+    }
+
+    #[test]
+    fn legacy_file_without_version_loads() {
+        let txt = r#"{
+            "save_path": null,
+            "questions": [{"id":1,"name":"Q1","full_score":10,"weight":1.0,"comment":""}],
+            "students": [{"id":"S1","name":"A"}],
+            "scores": [{"student_id":"S1","question_id":1,"score":7}],
+            "ratings": []
+        }"#;
+        let cfg = Config::from_json(txt).unwrap();
+        assert_eq!(cfg.schema_version, schema::CURRENT_VERSION);
+        assert_eq!(cfg.scores.value("S1", 1), Some(7.0));
+        assert_eq!(cfg.questions[0].step, 1.0);
+    }
+
+    #[test]
+    fn rubric_ticks_derive_the_score() {
+        let item = |id, points, deduction| RubricItem { id, criterion: String::new(), points, deduction };
+        let mut q = Question { id: 1, full_score: 10, rubric: vec![item(1, 4.0, false), item(2, 6.0, false), item(3, 1.5, true)], ..Default::default() };
+        let mut store = ScoreStore::new();
+        store.set_ticked("S1", &q, vec![1, 3]);
+        assert_eq!(store.value("S1", 1), Some(2.5));
+
+        // items removed from the rubric are dropped from the ticks
+        q.rubric.remove(0);
+        assert_eq!(store.rescore(&q), 1);
+        assert_eq!(store.ticked("S1", 1), Some(&[3][..]));
+        assert_eq!(store.value("S1", 1), Some(0.0));
+
+        // deductions only: start from full marks
+        q.rubric.remove(0);
+        assert_eq!(q.rubric_score(&[3]), 8.5);
+
+        store.set("S1", 1, Some(7.0));
+        assert_eq!(store.ticked("S1", 1), None);
+    }
+
+    #[test]
+    fn snippets_add_feedback_and_deduct() {
+        let snippet = |id, text: &str, deduction| Snippet { id, text: text.into(), deduction };
+        let mut q = Question {
+            id: 1,
+            full_score: 10,
+            snippets: vec![snippet(1, "単位なし", 1.0), snippet(2, "符号ミス", 0.5)],
+            ..Default::default()
+        };
+        let mut store = ScoreStore::new();
+        store.set("S1", 1, Some(10.0));
+        assert!(store.use_snippet("S1", &q, 1));
+        assert!(!store.use_snippet("S1", &q, 1));
+        assert!(store.use_snippet("S1", &q, 2));
+        // a blank cell gets the comment but no score
+        assert!(store.use_snippet("S2", &q, 2));
+        assert_eq!(store.value("S2", 1), None);
+        assert_eq!(store.value("S1", 1), Some(8.5));
+        assert_eq!(store.get("S1", 1).unwrap().feedback, "単位なし\n符号ミス");
+        assert_eq!(store.snippet_usage(1), HashMap::from([(1, 1), (2, 2)]));
+
+        // editing a used snippet does not change what undoing it gives back
+        q.snippets[0] = snippet(1, "単位が抜けています", 3.0);
+        assert!(store.unuse_snippet("S1", 1, 1));
+        assert_eq!(store.value("S1", 1), Some(9.5));
+        assert_eq!(store.get("S1", 1).unwrap().feedback, "符号ミス");
+
+        // retyping the score drops the link, so unusing cannot give points back twice
+        store.set("S1", 1, Some(7.0));
+        assert!(!store.unuse_snippet("S1", 1, 2));
+        assert_eq!(store.value("S1", 1), Some(7.0));
+
+        // only the points actually taken off come back
+        store.set("S3", 1, Some(2.0));
+        assert!(store.use_snippet("S3", &q, 1));
+        assert_eq!(store.value("S3", 1), Some(0.0));
+        assert!(store.unuse_snippet("S3", 1, 1));
+        assert_eq!(store.value("S3", 1), Some(2.0));
+
+        store.purge_snippet(1, 2);
+        assert_eq!(store.snippet_usage(1), HashMap::new());
+    }
 
+    #[test]
+    fn scores_follow_the_question_step() {
+        let q = Question { full_score: 5, step: 0.25, ..Default::default() };
+        assert!(q.accepts(2.75));
+        assert!(!q.accepts(2.6));
+        assert!(q.accepts(5.25));
+        assert!(q.over_full(5.25));
+        assert_eq!(q.snap(2.6), 2.5);
+    }
+
+    #[test]
+    fn score_store_set_and_retain() {
+        let mut store = ScoreStore::new();
+        store.set("S1", 1, Some(2.0));
+        store.set("S1", 2, Some(4.0));
+        store.set("S1", 1, Some(7.0));
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.value("S1", 1), Some(7.0));
+
+        store.retain(|sc| sc.question_id != 1);
+        assert_eq!(store.get("S1", 1).map(|sc| sc.score), None);
+        assert_eq!(store.value("S1", 2), Some(4.0));
+    }
+
+    #[test]
+    fn renaming_a_student_moves_scores() {
+        let mut cfg = Config::new();
+        cfg.students = vec![
+            Student { id: "A01".into(), name: String::new() },
+            Student { id: "A02".into(), name: String::new() },
+        ];
+        cfg.scores.set("A01", 1, Some(5.0));
+        cfg.scores.set("A02", 1, Some(8.0));
+
+        assert_eq!(cfg.rename_student(0, "A10"), 1);
+        assert_eq!(cfg.scores.value("A10", 1), Some(5.0));
+
+        // merging into an existing id keeps that student's marks
+        cfg.scores.set("A10", 2, Some(3.0));
+        assert_eq!(cfg.merge_student(0, "A02"), 1);
+        assert_eq!(cfg.students.len(), 1);
+        assert_eq!(cfg.scores.value("A02", 1), Some(8.0));
+        assert_eq!(cfg.scores.value("A02", 2), Some(3.0));
+    }
+
+    #[test]
+    fn question_edits_keep_parts_attached() {
+        let q = |id, parent_id| Question { id, parent_id, ..Default::default() };
+        let mut cfg = Config::new();
+        cfg.questions = vec![q(1, None), q(2, Some(1)), q(3, Some(2)), q(4, Some(2))];
+
+        cfg.rename_question(0, 10);
+        assert_eq!(cfg.questions[1].parent_id, Some(10));
+
+        // merging a group into one of its parts must not make that part its own parent
+        cfg.merge_question(1, 3);
+        assert_eq!(cfg.questions[1].parent_id, None);
+        assert_eq!(cfg.questions[2].parent_id, Some(3));
+
+        // deleting a group moves its parts up a level
+        cfg.remove_question(1);
+        assert_eq!(cfg.questions[1].parent_id, None);
+    }
+
+    #[test]
+    fn score_store_rename_keeps_existing_values() {
+        let mut store = ScoreStore::new();
+        store.set("OLD", 1, Some(1.0));
+        store.set("OLD", 2, Some(2.0));
+        store.set("NEW", 2, Some(9.0));
+        assert_eq!(store.rename_student("OLD", "NEW"), 1);
+        assert_eq!(store.value("NEW", 1), Some(1.0));
+        assert_eq!(store.value("NEW", 2), Some(9.0));
+        assert!(store.get("OLD", 1).is_none());
+
+        assert_eq!(store.rename_question(1, 3), 1);
+        assert_eq!(store.value("NEW", 3), Some(1.0));
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context};
+use dioxus::prelude::*;
+use tokio::fs;
+use crate::grading::{self, format_score, Hierarchy, StudentResult};
+use crate::models::{Config, TableRow};
+
+// Individual reports: one text file per student with the scores and the
+// feedback of every question. Private grader notes are never included.
+// The row comes from table_rows over the whole class, so that relative
+// ratings see the full distribution.
+
+pub fn student_report(config: &Config, result: &StudentResult, row: &TableRow) -> String {
+    let questions = &config.questions;
+    let tree = Hierarchy::new(questions);
+    let mut out = String::new();
+
+    out.push_str(&format!("{} {}\n\n", result.student_id, result.student_name));
+
+    for (i, q) in questions.iter().enumerate() {
+        let indent = "  ".repeat(tree.depth(i));
+        let value = match result.statuses[i] {
+            Some(st) => st.label().to_string(),
+            None => result.scores[i].map(format_score).unwrap_or_else(|| "-".to_string()),
+        };
+        // the student's own full score, as in the grid (picked parts of a best-N group)
+        let full = format_score(result.fulls[i]);
+        out.push_str(&format!("{indent}{}: {value} / {full}\n", q.name));
+
+        let Some(sc) = config.scores.get(&result.student_id, q.id) else {
+            continue;
+        };
+        if let Some(ticked) = &sc.ticked {
+            for item in q.rubric.iter().filter(|r| ticked.contains(&r.id)) {
+                let sign = if item.deduction { "-" } else { "+" };
+                out.push_str(&format!("{indent}  [{sign}{}] {}\n", format_score(item.points), item.criterion));
+            }
+        }
+        for line in sc.feedback.lines().filter(|l| !l.trim().is_empty()) {
+            out.push_str(&format!("{indent}  > {line}\n"));
+        }
+    }
+
+    out.push('\n');
+    if !row.adjustment.is_empty() {
+        out.push_str(&format!("調整: {}\n", row.adjustment));
+    }
+    if row.final_display.is_empty() {
+        out.push_str("合計: 未完了\n");
+    } else if row.rating.is_empty() {
+        out.push_str(&format!("合計: {}\n", row.final_display));
+    } else {
+        out.push_str(&format!("合計: {} ({})\n", row.final_display, row.rating));
+    }
+    out
+}
+
+// File names for student ids, safe on every platform.
+// Ids that sanitize to the same name ("A/1", "A_1", "a_1") get a counter, so no report is overwritten.
+pub fn report_file_names<'a>(student_ids: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut taken = HashSet::new();
+    student_ids.into_iter().map(|id| {
+        let stem: String = id.chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let stem = if stem.is_empty() { "_".to_string() } else { stem };
+        // case-insensitive: Windows and macOS file systems are
+        let mut name = stem.clone();
+        let mut n = 1;
+        while !taken.insert(name.to_lowercase()) {
+            n += 1;
+            name = format!("{stem}_{n}");
+        }
+        format!("{name}.txt")
+    }).collect()
+}
+
+// Write a report for every student into the directory; returns the number of files
+pub async fn write_reports<P: AsRef<Path>>(config: &Config, dir: P) -> Result<usize> {
+    let dir = dir.as_ref();
+    let results = grading::evaluate(config);
+    let rows = grading::table_rows(config, &results);
+    let names = report_file_names(results.iter().map(|r| r.student_id.as_str()));
+    for ((r, row), name) in results.iter().zip(rows.iter()).zip(names) {
+        let path = dir.join(name);
+        fs::write(&path, student_report(config, r, row))
+            .await
+            .with_context(|| format!("Failed to write file: {:?}", path))?;
+    }
+    Ok(results.len())
+}
+
+// Pick a folder and write the individual reports into it
+pub fn export_reports(config: Signal<Config>, mut msg: Signal<String>) {
+    spawn(async move {
+        let handle = rfd::AsyncFileDialog::new().pick_folder().await;
+
+        let Some(handle) = handle else {
+            return;
+        };
+
+        let dir: PathBuf = handle.path().to_path_buf();
+        let cfg_snapshot = config();
+        match write_reports(&cfg_snapshot, &dir).await {
+            Ok(n) => msg.set(format!("Reports: {n} files in {:?}", dir)),
+            Err(e) => msg.set(format!("Report failed: {:#}", e)),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Question, Student};
+
+    #[test]
+    fn report_has_feedback_but_not_notes() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![Question { id: 1, name: "Q1".into(), full_score: 10, ..Default::default() }];
+        cfg.students = vec![Student { id: "A/1".into(), name: "山田".into() }];
+        cfg.scores.set("A/1", 1, Some(7.5));
+        cfg.scores.set_feedback("A/1", 1, "単位が抜けています".into());
+        cfg.scores.set_note("A/1", 1, "再確認".into());
+
+        let r = grading::student_result(&cfg, "A/1").unwrap();
+        let row = grading::table_rows(&cfg, std::slice::from_ref(&r)).remove(0);
+        let text = student_report(&cfg, &r, &row);
+        assert!(text.contains("Q1: 7.5 / 10\n  > 単位が抜けています\n"));
+        assert!(!text.contains("再確認"));
+        assert!(text.ends_with("合計: 75\n"));
+        assert_eq!(
+            report_file_names(["A/1", "A_1", "a_1", "A_1_2", ""]),
+            vec!["A_1.txt", "A_1_2.txt", "a_1_3.txt", "A_1_2_2.txt", "_.txt"],
+        );
+    }
+
+    #[test]
+    fn group_full_score_follows_the_picked_parts() {
+        use crate::models::{Choice, ChoiceRule};
+        let mut cfg = Config::new();
+        cfg.questions = vec![Question {
+            id: 1,
+            name: "Q1".into(),
+            choice: Some(Choice { rule: ChoiceRule::Chosen, count: 1 }),
+            ..Default::default()
+        }];
+        for (id, full) in [(11, 4), (12, 10)] {
+            cfg.questions.push(Question { id, name: format!("Q{id}"), full_score: full, parent_id: Some(1), ..Default::default() });
+        }
+        cfg.students = vec![Student { id: "A1".into(), name: String::new() }];
+        cfg.scores.set("A1", 11, Some(3.0));
+
+        let r = grading::student_result(&cfg, "A1").unwrap();
+        let row = grading::table_rows(&cfg, std::slice::from_ref(&r)).remove(0);
+        let text = student_report(&cfg, &r, &row);
+        assert!(text.contains("Q1: 3 / 4\n"));
+        assert!(text.contains("合計: 75\n"));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use serde_json::{json, Value};
+
+// Config file schema versions.
+// Files without `schema_version` are version 0 (written before versioning).
+// Every change of the file format bumps CURRENT_VERSION and appends one step
+// to MIGRATIONS, which upgrades a file from version i to i + 1.
+
+pub const CURRENT_VERSION: u32 = 13;
+
+pub const VERSION_KEY: &str = "schema_version";
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+const MIGRATIONS: &[Migration] = &[
+    // 0 -> 1: version field only
+    |_| Ok(()),
+    // 1 -> 2: Question.parent_id (absent = top-level)
+    |_| Ok(()),
+    // 2 -> 3: decimal scores and Question.step (integers still parse, step defaults to 1)
+    |_| Ok(()),
+    // 3 -> 4: Score.status and Config.status_policy (absent = none / default policy)
+    |_| Ok(()),
+    // 4 -> 5: Question.rubric and Score.ticked (absent = no rubric / entered by hand)
+    |_| Ok(()),
+    // 5 -> 6: Score.note and Score.feedback (absent = empty)
+    |_| Ok(()),
+    // 6 -> 7: Question.snippets and Score.snippets (absent = empty)
+    |_| Ok(()),
+    // 7 -> 8: Question.bonus and Config.cap_policy (absent = normal question / cap at 100)
+    |_| Ok(()),
+    // 8 -> 9: Question.min_score and Config.adjustments (absent = 0 / none)
+    |_| Ok(()),
+    // 9 -> 10: Question.choice (absent = every part counts)
+    |_| Ok(()),
+    // 10 -> 11: Config.rounding (absent = half-up to an integer, as before)
+    |_| Ok(()),
+    // 11 -> 12: Config.rating_mode, Config.tie_policy and Rating.quota (absent = absolute cut-offs)
+    |_| Ok(()),
+    // 12 -> 13: Score.snippets records the text and deduction applied (was snippet ids)
+    record_used_snippets,
+];
+
+// Old entries take the current text and deduction of their snippet; the best guess left
+fn record_used_snippets(value: &mut Value) -> Result<(), String> {
+    let mut snippets: HashMap<u64, (Vec<Value>, bool)> = HashMap::new();
+    for q in value.get("questions").and_then(Value::as_array).into_iter().flatten() {
+        let Some(id) = q.get("id").and_then(Value::as_u64) else {
+            continue;
+        };
+        let list = q.get("snippets").and_then(Value::as_array).cloned().unwrap_or_default();
+        let rubric = q.get("rubric").and_then(Value::as_array).is_some_and(|r| !r.is_empty());
+        snippets.insert(id, (list, rubric));
+    }
+
+    let Some(scores) = value.get_mut("scores").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for sc in scores.iter_mut() {
+        let question_id = sc.get("question_id").and_then(Value::as_u64);
+        let scored = sc.get("score").is_some_and(|v| !v.is_null());
+        let Some(used) = sc.get_mut("snippets").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for entry in used.iter_mut() {
+            let Some(id) = entry.as_u64() else {
+                return Err(format!("Invalid snippet id: {entry}"));
+            };
+            let found = question_id.and_then(|q| snippets.get(&q)).and_then(|(list, rubric)| {
+                let sn = list.iter().find(|sn| sn.get("id").and_then(Value::as_u64) == Some(id))?;
+                Some((sn, *rubric))
+            });
+            let text = found.and_then(|(sn, _)| sn.get("text")).cloned().unwrap_or_else(|| Value::from(""));
+            let deduction = match found {
+                Some((sn, false)) if scored => sn.get("deduction").cloned().unwrap_or_else(|| Value::from(0.0)),
+                _ => Value::from(0.0),
+            };
+            *entry = json!({ "id": id, "text": text, "deduction": deduction });
+        }
+    }
+    Ok(())
+}
+
+// Schema version of a parsed config file
+pub fn version_of(value: &Value) -> Result<u32, String> {
+    match value.get(VERSION_KEY) {
+        None | Some(Value::Null) => Ok(0),
+        Some(v) => v.as_u64()
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or_else(|| format!("Invalid {VERSION_KEY}: {v}")),
+    }
+}
+
+// Upgrade a parsed config file to CURRENT_VERSION.
+// Returns the version the file was written with.
+pub fn migrate(value: &mut Value) -> Result<u32, String> {
+    if !value.is_object() {
+        return Err("Config file must be a JSON object.".to_string());
+    }
+    let from = version_of(value)?;
+    if from > CURRENT_VERSION {
+        return Err(format!(
+            "This file was saved by a newer version of the app (schema {from}); \
+             this version supports up to schema {CURRENT_VERSION}. Please update the app."
+        ));
+    }
+    for (v, step) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        step(value).map_err(|e| format!("Migration from schema {v} failed: {e}"))?;
+        value[VERSION_KEY] = Value::from(v as u32 + 1);
+    }
+    Ok(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_cover_every_version() {
+        assert_eq!(MIGRATIONS.len(), CURRENT_VERSION as usize);
+    }
+
+    #[test]
+    fn unversioned_file_is_upgraded() {
+        let mut v = json!({ "questions": [] });
+        assert_eq!(migrate(&mut v), Ok(0));
+        assert_eq!(version_of(&v), Ok(CURRENT_VERSION));
+    }
+
+    #[test]
+    fn used_snippet_ids_become_records() {
+        let mut v = json!({
+            "schema_version": 12,
+            "questions": [{ "id": 1, "snippets": [{ "id": 2, "text": "単位なし", "deduction": 1.0 }] }],
+            "scores": [
+                { "student_id": "S1", "question_id": 1, "score": 9, "snippets": [2] },
+                { "student_id": "S2", "question_id": 1, "score": null, "snippets": [2, 7] },
+            ],
+        });
+        migrate(&mut v).unwrap();
+        assert_eq!(v["scores"][0]["snippets"], json!([{ "id": 2, "text": "単位なし", "deduction": 1.0 }]));
+        assert_eq!(v["scores"][1]["snippets"][0]["deduction"], json!(0.0));
+        assert_eq!(v["scores"][1]["snippets"][1], json!({ "id": 7, "text": "", "deduction": 0.0 }));
+    }
+
+    #[test]
+    fn newer_file_is_refused() {
+        let mut v = json!({ "schema_version": CURRENT_VERSION + 1 });
+        let err = migrate(&mut v).unwrap_err();
+        assert!(err.contains("newer version"));
+    }
+}
@@ -0,0 +1,97 @@
+use dioxus::prelude::*;
+use crate::grading;
+use crate::models::{Adjustment, Config};
+use crate::ui::keep_out_of_hotkeys;
+
+// Late penalty, deductions and misconduct of the current student
+#[component]
+pub fn AdjustmentPanel(
+    config: Signal<Config>,
+    student_id: String,
+) -> Element {
+
+    let (adj, result) = {
+        let cfg = config.read();
+        (
+            cfg.adjustment(&student_id).cloned().unwrap_or_default(),
+            grading::student_result(&cfg, &student_id),
+        )
+    };
+    let raw = result.as_ref().and_then(|r| Some(r.rounding.format(r.raw_percent?)));
+    let fin = result.as_ref().and_then(|r| r.final_display());
+    let mut open = use_signal(|| false);
+    let active = !adj.is_empty();
+
+    let (late_sid, deduction_sid, misconduct_sid, reason_sid) =
+        (student_id.clone(), student_id.clone(), student_id.clone(), student_id.clone());
+
+    rsx! {
+        div { class: "mt-3 border-t border-base-300 pt-2",
+            // keep the grading hotkeys out of these fields
+            onkeydown: move |e| keep_out_of_hotkeys(&e),
+            div { class: "flex items-center gap-2",
+                button {
+                    class: if active { "btn btn-xs btn-error btn-outline" } else { "btn btn-xs btn-ghost" },
+                    onclick: move |_| open.toggle(),
+                    if active { "調整あり" } else { "調整" }
+                }
+                if active {
+                    span { class: "text-sm text-error", "{adj.summary()}" }
+                    if let (Some(raw), Some(fin)) = (raw, fin) {
+                        span { class: "text-sm opacity-60", "{raw} → {fin}" }
+                    }
+                }
+            }
+            if open() {
+                div { class: "grid grid-cols-[8rem_1fr] gap-2 items-center mt-2",
+                    span { class: "text-sm", "遅延減点 (%)" }
+                    input {
+                        class: "input input-bordered input-sm w-28",
+                        r#type: "number",
+                        min: "0",
+                        max: "100",
+                        value: "{adj.late_penalty}",
+                        oninput: move |e| {
+                            let v = e.value().parse::<f32>().unwrap_or(0.0).clamp(0.0, 100.0);
+                            edit(config, &late_sid, |a| a.late_penalty = v);
+                        }
+                    }
+                    span { class: "text-sm", title: "最終得点（%）から差し引くポイント", "減点 (%pt)" }
+                    input {
+                        class: "input input-bordered input-sm w-28",
+                        r#type: "number",
+                        min: "0",
+                        value: "{adj.deduction}",
+                        oninput: move |e| {
+                            let v = e.value().parse::<f32>().unwrap_or(0.0).max(0.0);
+                            edit(config, &deduction_sid, |a| a.deduction = v);
+                        }
+                    }
+                    span { class: "text-sm", "不正行為" }
+                    input {
+                        r#type: "checkbox",
+                        class: "checkbox checkbox-sm checkbox-error",
+                        checked: adj.misconduct,
+                        onchange: move |e| {
+                            let v = e.checked();
+                            edit(config, &misconduct_sid, |a| a.misconduct = v);
+                        }
+                    }
+                    span { class: "text-sm", "理由" }
+                    input {
+                        class: "input input-bordered input-sm w-full",
+                        value: "{adj.reason}",
+                        oninput: move |e| {
+                            let v = e.value();
+                            edit(config, &reason_sid, |a| a.reason = v);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn edit(mut config: Signal<Config>, student_id: &str, f: impl FnOnce(&mut Adjustment)) {
+    config.write().edit_adjustment(student_id, f);
+}
@@ -0,0 +1,83 @@
+use dioxus::prelude::*;
+use std::path::PathBuf;
+use crate::backup::{self, BackupEntry};
+use crate::models::Config;
+
+#[component]
+pub fn BackupDialog(
+    is_open: Signal<bool>,
+    config: Signal<Config>,
+    msg: Signal<String>,
+) -> Element {
+
+    let save_path = config.read().save_path.clone();
+
+    let backups = use_resource(move || {
+        let save_path = save_path.clone();
+        async move {
+            match save_path {
+                Some(p) => backup::list_backups(&PathBuf::from(p)).await,
+                None => Vec::new(),
+            }
+        }
+    });
+    let list: Option<Vec<BackupEntry>> = backups.read().clone();
+
+    rsx! {
+        div { class: "modal modal-open",
+            div { class: "modal-box w-11/12 max-w-2xl",
+                div { class: "flex items-center gap-3",
+                    h3 { class: "font-bold text-lg", "バックアップ" }
+                    div { class: "ml-auto",
+                        button {
+                            class: "btn btn-sm",
+                            onclick: move |_| is_open.set(false),
+                            "閉じる"
+                        }
+                    }
+                }
+
+                p { class: "text-sm opacity-70 mt-2",
+                    "保存のたびに直前の {backup::KEEP} 世代までを保存先の "
+                    code { ".backups" }
+                    " フォルダに残しています。復元後に Save すると現在のファイルが置き換わります。"
+                }
+
+                div { class: "mt-3 max-h-80 overflow-auto",
+                    match list {
+                        None => rsx! { div { class: "opacity-60", "Loading..." } },
+                        Some(list) if list.is_empty() => rsx! {
+                            div { class: "opacity-60", "バックアップはありません" }
+                        },
+                        Some(list) => rsx! {
+                            table { class: "table table-sm",
+                                tbody {
+                                    for b in list.into_iter() {
+                                        tr {
+                                            td { class: "font-mono", "{b.label}" }
+                                            td { class: "text-right opacity-60", "{b.size / 1024} KB" }
+                                            td { class: "text-right",
+                                                button {
+                                                    class: "btn btn-xs",
+                                                    onclick: move |_| {
+                                                        Config::restore_backup(config, b.path.clone(), msg);
+                                                        is_open.set(false);
+                                                    },
+                                                    "復元"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                    }
+                }
+            }
+
+            div { class: "modal-backdrop",
+                onclick: move |_| is_open.set(false),
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 use crate::models::Config;
+use crate::ui::SnippetBank;
 
 #[component]
 pub fn CommentPanel(
@@ -42,6 +43,10 @@ pub fn CommentPanel(
                     onchange: on_change,
                 }
 
+                if let Some(question_id) = cur_question_id {
+                    SnippetBank { config, question_id }
+                }
+
             }
         }
     }
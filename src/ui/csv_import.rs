@@ -0,0 +1,131 @@
+use dioxus::prelude::*;
+use crate::import::{apply_students, CsvTable, ImportMode};
+use crate::models::Config;
+
+const PREVIEW_ROWS: usize = 10;
+
+#[component]
+pub fn CsvImportPanel(
+    config: Signal<Config>,
+    table: Signal<Option<CsvTable>>,
+    msg: Signal<String>,
+) -> Element {
+
+    // hooks before the early return; the panel is keyed per import, so they start from each new table
+    let guess = move || table.peek().as_ref().map(|t| t.guess_columns()).unwrap_or_default();
+    let mut has_header = use_signal(|| table.peek().as_ref().is_some_and(|t| t.looks_like_header()));
+    let mut id_col = use_signal(|| guess().0.unwrap_or(0));
+    let mut name_col = use_signal(|| guess().1);
+
+    let Some(t) = table() else {
+        return rsx! {};
+    };
+
+    let columns = t.column_names(has_header());
+    let students = t.students(has_header(), id_col(), name_col());
+    let preview = t.data_rows(has_header()).iter().take(PREVIEW_ROWS).cloned().collect::<Vec<_>>();
+    let total_rows = t.data_rows(has_header()).len();
+    let delimiter = if t.delimiter == b'\t' { "TSV" } else { "CSV" };
+
+    let mut apply = move |mode: ImportMode| {
+        let Some(t) = table() else {
+            return;
+        };
+        let imported = t.students(has_header(), id_col(), name_col());
+        let (added, updated) = apply_students(&mut config.write().students, imported, mode);
+        msg.set(format!("Imported: {added} added, {updated} updated"));
+        table.set(None);
+    };
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body p-2 space-y-3",
+
+                div { class: "flex flex-wrap items-center gap-3",
+                    div { class: "card-title", "CSV import" }
+                    span { class: "badge", "{delimiter}" }
+                    span { class: "badge", "{t.encoding}" }
+
+                    label { class: "label cursor-pointer gap-2",
+                        input {
+                            r#type: "checkbox",
+                            class: "checkbox checkbox-sm",
+                            checked: has_header(),
+                            onchange: move |e| has_header.set(e.checked()),
+                        }
+                        span { class: "text-sm", "1行目は見出し" }
+                    }
+
+                    label { class: "text-sm", "id" }
+                    select {
+                        class: "select select-sm select-bordered",
+                        onchange: move |e| {
+                            if let Ok(c) = e.value().parse::<usize>() {
+                                id_col.set(c);
+                            }
+                        },
+                        for (i, c) in columns.iter().enumerate() {
+                            option { value: "{i}", selected: i == id_col(), "{c}" }
+                        }
+                    }
+
+                    label { class: "text-sm", "name" }
+                    select {
+                        class: "select select-sm select-bordered",
+                        onchange: move |e| name_col.set(e.value().parse::<usize>().ok()),
+                        option { value: "", selected: name_col().is_none(), "(なし)" }
+                        for (i, c) in columns.iter().enumerate() {
+                            option { value: "{i}", selected: name_col() == Some(i), "{c}" }
+                        }
+                    }
+                }
+
+                div { class: "overflow-x-auto max-h-72",
+                    table { class: "table table-xs",
+                        thead {
+                            tr {
+                                for (i, c) in columns.iter().enumerate() {
+                                    th {
+                                        class: if i == id_col() || name_col() == Some(i) { "bg-base-200" } else { "" },
+                                        "{c}"
+                                    }
+                                }
+                            }
+                        }
+                        tbody {
+                            for row in preview.iter() {
+                                tr {
+                                    for cell in row.iter() {
+                                        td { "{cell}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "flex flex-wrap items-center gap-2",
+                    span { class: "text-sm opacity-70",
+                        "{students.len()} students / {total_rows} rows"
+                    }
+                    div { class: "flex-1" }
+                    button {
+                        class: "btn btn-sm btn-primary",
+                        onclick: move |_| apply(ImportMode::Merge),
+                        "Merge"
+                    }
+                    button {
+                        class: "btn btn-sm btn-warning",
+                        onclick: move |_| apply(ImportMode::Replace),
+                        "Replace"
+                    }
+                    button {
+                        class: "btn btn-sm btn-ghost",
+                        onclick: move |_| table.set(None),
+                        "Cancel"
+                    }
+                }
+            }
+        }
+    }
+}
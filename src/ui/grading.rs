@@ -11,7 +11,7 @@ pub fn GradingPage(
     
     let cur_student_idx = use_signal(|| 0usize);
     let search_open = use_signal(|| false);    
-    let msg = use_signal(|| String::new());
+    let msg = use_signal(String::new);
 
     use_effect(move || {
         let el_id = if search_open() { "search" } else { "score-0" };
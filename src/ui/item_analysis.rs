@@ -0,0 +1,104 @@
+use dioxus::prelude::*;
+use crate::analysis::{self, ItemStats};
+use crate::grading::format_score;
+use crate::models::{Config, Page};
+use crate::ui::TopBar;
+
+#[component]
+pub fn ItemAnalysisPage(
+    on_nav: EventHandler<Page>,
+    config: Signal<Config>,
+) -> Element {
+
+    let stats = analysis::item_analysis(&config.read());
+    let flagged = stats.iter().filter(|s| s.is_flagged()).count();
+
+    rsx! {
+        div { class: "min-h-screen p-2 bg-base-200",
+
+            TopBar { config, on_nav }
+
+            div { class: "card bg-base-100 shadow",
+                div { class: "card-body p-2",
+                    div { class: "flex items-baseline gap-2",
+                        div { class: "card-title", "問題分析" }
+                        span { class: "text-sm opacity-60", "上位・下位 27% で識別力を計算（調整前の合計点）" }
+                        if flagged > 0 {
+                            span { class: "badge badge-error", "識別力が負: {flagged} 問" }
+                        }
+                    }
+                    div { class: "overflow-x-auto",
+                        table { class: "table table-sm",
+                            thead {
+                                tr {
+                                    th { "問題" }
+                                    th { class: "text-right", "満点" }
+                                    th { class: "text-right", "n" }
+                                    th { class: "text-right", "平均" }
+                                    th { class: "text-right", "SD" }
+                                    th { class: "text-right", "正答率" }
+                                    th { class: "text-right", "識別力" }
+                                    th { class: "text-right", "点双列相関" }
+                                    th { "分布" }
+                                }
+                            }
+                            tbody {
+                                for s in stats.into_iter() {
+                                    ItemRow { key: "{s.question_id}", stats: s }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ItemRow(stats: ItemStats) -> Element {
+    let s = &stats;
+    let fixed = |v: Option<f32>, digits: usize| v.map(|v| format!("{v:.digits$}")).unwrap_or_else(|| "-".to_string());
+    let mean = fixed(s.mean, 2);
+    let sd = fixed(s.sd, 2);
+    let facility = s.facility.map(|v| format!("{:.0}%", v * 100.0)).unwrap_or_else(|| "-".to_string());
+    let discrimination = fixed(s.discrimination, 2);
+    let point_biserial = fixed(s.point_biserial, 2);
+    let depth = s.depth;
+    let max = s.distribution.iter().map(|d| d.1).max().unwrap_or(1);
+
+    rsx! {
+        tr { class: if s.is_flagged() { "bg-error/10" } else { "" },
+            td { style: "padding-left: {depth}rem;",
+                if s.is_flagged() {
+                    span { class: "text-error mr-1", title: "識別力が負です。設問や採点を確認してください", "⚠" }
+                }
+                "{s.name}"
+            }
+            td { class: "text-right font-mono", "{s.full_score}" }
+            td { class: "text-right font-mono", "{s.n}" }
+            td { class: "text-right font-mono", "{mean}" }
+            td { class: "text-right font-mono", "{sd}" }
+            td { class: "text-right font-mono", "{facility}" }
+            td {
+                class: if s.discrimination.is_some_and(|d| d < 0.0) { "text-right font-mono text-error" } else { "text-right font-mono" },
+                "{discrimination}"
+            }
+            td {
+                class: if s.point_biserial.is_some_and(|r| r < 0.0) { "text-right font-mono text-error" } else { "text-right font-mono" },
+                "{point_biserial}"
+            }
+            td {
+                div { class: "flex items-end gap-px h-8",
+                    for (value, count) in s.distribution.iter().copied() {
+                        div {
+                            class: "bg-primary w-2",
+                            style: "height: {count as f32 / max as f32 * 100.0}%;",
+                            title: "{format_score(value)} 点: {count} 人",
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use dioxus::prelude::*;
 use crate::Page;
-use crate::models::{Config, Question};
-use crate::ui::TopBar;
+use crate::grading::Hierarchy;
+use crate::models::{Choice, ChoiceRule, Config, Question};
+use crate::ui::{RubricEditor, TopBar};
 
 #[component]
 pub fn MasterQuestionsPage(
@@ -39,8 +40,7 @@ pub fn MasterQuestionsPage(
                                     }
 
                                     // 通常の Config 形式として読むが questions だけ使う
-                                    let parsed: Result<Config, _> = serde_json::from_str(&txt);
-                                    let Ok(cfg) = parsed else {
+                                    let Ok(cfg) = Config::from_json(&txt) else {
                                         return;
                                     };
 
@@ -62,10 +62,7 @@ pub fn MasterQuestionsPage(
 
                                 qs.push(Question {
                                     id: next_id,
-                                    name: String::new(),
-                                    full_score: 0,
-                                    weight: 1.0,
-                                    comment: String::new(),
+                                    ..Default::default()
                                 });
 
                                 config.write().questions = qs;
@@ -101,9 +98,11 @@ pub fn MasterQuestionsPage(
                                     th { class: "w-24", "id" }
                                     th { class: "w-64", "name" }
                                     th { class: "w-32", "full_score" }
+                                    th { class: "w-24", "step" }
                                     th { class: "w-32", "weight" }
+                                    th { class: "w-32", "parent" }
                                     th { "comment" }
-                                    th { class: "w-24", "" }
+                                    th { class: "w-48", "" }
                                 }
                             }
                             tbody {
@@ -128,17 +127,85 @@ pub fn QuestionRow(
     let Some(q) = q else {
         return rsx! {};
     };
+    let duplicated = config.read().questions.iter().filter(|x| x.id == q.id).count() > 1;
+
+    // groups take their full score from their parts; parts are weighted through the group
+    let (group_full, parent_options) = {
+        let cfg = config.read();
+        let h = Hierarchy::new(&cfg.questions);
+        let group_full = h.is_group(idx).then(|| h.full_score(&cfg.questions, idx));
+        let options: Vec<u32> = cfg.questions.iter()
+            .enumerate()
+            .filter(|(i, _)| *i != idx && !h.has_ancestor_in(*i, &[idx]))
+            .map(|(_, x)| x.id)
+            .collect();
+        (group_full, options)
+    };
+    let is_part = q.parent_id.is_some();
+    let mut rubric_open = use_signal(|| false);
+    let rubric_len = q.rubric.len();
+
+    // id edits are committed on change and renamed into the scores
+    let mut draft = use_signal(|| None::<String>);
+    let mut clash = use_signal(|| None::<u32>);
+    let id_value = draft().unwrap_or_else(|| q.id.to_string());
 
     rsx! {
         tr {
             td {
                 input {
-                    class: "input input-bordered input-sm w-20",
+                    class: if duplicated { "input input-bordered input-sm w-20 input-error" } else { "input input-bordered input-sm w-20" },
+                    title: if duplicated { "id が重複しています" } else { "" },
                     r#type: "number",
-                    value: "{q.id}",
-                    oninput: move |ev| {
-                        let v = ev.value().parse::<u32>().unwrap_or(0);
-                        config.write().questions.get_mut(idx).unwrap().id = v;
+                    value: "{id_value}",
+                    oninput: move |ev| draft.set(Some(ev.value())),
+                    onchange: move |ev| {
+                        let Ok(new_id) = ev.value().trim().parse::<u32>() else {
+                            draft.set(None);
+                            return;
+                        };
+                        let taken = {
+                            let cfg = config.read();
+                            let Some(cur) = cfg.questions.get(idx) else {
+                                return;
+                            };
+                            if cur.id == new_id {
+                                drop(cfg);
+                                draft.set(None);
+                                return;
+                            }
+                            cfg.questions.iter().enumerate().any(|(i, x)| i != idx && x.id == new_id)
+                        };
+                        if taken {
+                            clash.set(Some(new_id));
+                        } else {
+                            config.write().rename_question(idx, new_id);
+                            draft.set(None);
+                        }
+                    }
+                }
+                if let Some(target) = clash() {
+                    div { class: "flex flex-col gap-1 mt-1 text-xs",
+                        span { class: "text-warning", "{target} は既存の id です。点数を統合しますか？" }
+                        div { class: "flex gap-1",
+                            button {
+                                class: "btn btn-xs btn-warning",
+                                onclick: move |_| {
+                                    config.write().merge_question(idx, target);
+                                    clash.set(None);
+                                    draft.set(None);
+                                },
+                                "統合"
+                            }
+                            button {
+                                class: "btn btn-xs btn-ghost",
+                                onclick: move |_| {
+                                    clash.set(None);
+                                    draft.set(None);
+                                },
+                                "取消"
+                            }
+                        }
                     }
                 }
             }
@@ -152,14 +219,88 @@ pub fn QuestionRow(
                     }
                 }
             }
+            td {
+                if let Some(full) = group_full {
+                    input {
+                        class: "input input-bordered input-sm w-28",
+                        title: "小問の合計",
+                        disabled: true,
+                        value: "{full}",
+                    }
+                    div { class: "flex items-center gap-1 mt-1",
+                        select {
+                            class: "select select-bordered select-xs",
+                            title: "小問の集計方法",
+                            onchange: move |ev| {
+                                let mut cfg = config.write();
+                                let q = cfg.questions.get_mut(idx).unwrap();
+                                let count = q.choice.map_or(1, |c| c.count);
+                                q.choice = match ev.value().as_str() {
+                                    "best" => Some(Choice { rule: ChoiceRule::Best, count }),
+                                    "chosen" => Some(Choice { rule: ChoiceRule::Chosen, count }),
+                                    _ => None,
+                                };
+                            },
+                            option { value: "", selected: q.choice.is_none(), "全問" }
+                            option { value: "best", selected: q.choice.is_some_and(|c| c.rule == ChoiceRule::Best), "上位" }
+                            option { value: "chosen", selected: q.choice.is_some_and(|c| c.rule == ChoiceRule::Chosen), "選択" }
+                        }
+                        if let Some(choice) = q.choice {
+                            input {
+                                class: "input input-bordered input-xs w-14",
+                                r#type: "number",
+                                min: "1",
+                                title: "集計する小問の数",
+                                value: "{choice.count}",
+                                oninput: move |ev| {
+                                    if let Some(n) = ev.value().parse::<u32>().ok().filter(|n| *n > 0) {
+                                        if let Some(c) = config.write().questions.get_mut(idx).unwrap().choice.as_mut() {
+                                            c.count = n;
+                                        }
+                                    }
+                                }
+                            }
+                            span { class: "text-xs", "問" }
+                        }
+                    }
+                } else {
+                    input {
+                        class: "input input-bordered input-sm w-28",
+                        r#type: "number",
+                        value: "{q.full_score}",
+                        oninput: move |ev| {
+                            let v = ev.value().parse::<u32>().unwrap_or(0);
+                            config.write().questions.get_mut(idx).unwrap().full_score = v;
+                        }
+                    }
+                    input {
+                        class: "input input-bordered input-xs w-28 mt-1",
+                        r#type: "number",
+                        step: "0.5",
+                        max: "0",
+                        title: "最低点（負の値で誤答減点）",
+                        placeholder: "min",
+                        value: "{q.min_score}",
+                        oninput: move |ev| {
+                            let v = ev.value().parse::<f32>().unwrap_or(0.0).min(0.0);
+                            config.write().questions.get_mut(idx).unwrap().min_score = v;
+                        }
+                    }
+                }
+            }
             td {
                 input {
-                    class: "input input-bordered input-sm w-28",
+                    class: "input input-bordered input-sm w-20",
                     r#type: "number",
-                    value: "{q.full_score}",
+                    step: "0.05",
+                    min: "0",
+                    disabled: group_full.is_some(),
+                    title: "採点の刻み (0.5 = 半点)",
+                    value: "{q.step}",
                     oninput: move |ev| {
-                        let v = ev.value().parse::<u32>().unwrap_or(0);
-                        config.write().questions.get_mut(idx).unwrap().full_score = v;
+                        if let Some(v) = ev.value().parse::<f32>().ok().filter(|v| *v > 0.0) {
+                            config.write().questions.get_mut(idx).unwrap().step = v;
+                        }
                     }
                 }
             }
@@ -168,12 +309,40 @@ pub fn QuestionRow(
                     class: "input input-bordered input-sm w-28",
                     r#type: "number",
                     step: "0.01",
+                    disabled: is_part,
+                    title: if is_part { "小問は親の重みで集計されます" } else { "" },
                     value: "{q.weight}",
                     oninput: move |ev| {
                         let v = ev.value().parse::<f32>().unwrap_or(0.0);
                         config.write().questions.get_mut(idx).unwrap().weight = v;
                     }
                 }
+                if !is_part {
+                    label { class: "flex items-center gap-1 text-xs mt-1", title: "満点に含めない加点問題",
+                        input {
+                            r#type: "checkbox",
+                            class: "checkbox checkbox-xs",
+                            checked: q.bonus,
+                            onchange: move |ev| {
+                                config.write().questions.get_mut(idx).unwrap().bonus = ev.checked();
+                            }
+                        }
+                        "加点"
+                    }
+                }
+            }
+            td {
+                select {
+                    class: "select select-bordered select-sm w-28",
+                    onchange: move |ev| {
+                        let v = ev.value().parse::<u32>().ok();
+                        config.write().questions.get_mut(idx).unwrap().parent_id = v;
+                    },
+                    option { value: "", selected: q.parent_id.is_none(), "-" }
+                    for pid in parent_options.into_iter() {
+                        option { value: "{pid}", selected: q.parent_id == Some(pid), "{pid}" }
+                    }
+                }
             }
             td {
                 input {
@@ -185,22 +354,56 @@ pub fn QuestionRow(
                     }
                 }
             }
-            td { class: "text-right",
+            td { class: "text-right whitespace-nowrap",
                 button {
                     class: "btn btn-sm btn-ghost",
-                    onclick: move |_| {
-                        let mut config_write = config.write();
-                        if idx < config_write.questions.len() {
-                            config_write.questions.remove(idx);
-                        }
-                    },
+                    title: "小問を追加",
+                    onclick: move |_| add_part(config, idx),
+                    "＋小問"
+                }
+                button {
+                    class: if rubric_open() { "btn btn-sm btn-active" } else { "btn btn-sm btn-ghost" },
+                    title: "採点基準（ルーブリック）",
+                    disabled: group_full.is_some(),
+                    onclick: move |_| rubric_open.toggle(),
+                    "基準 ({rubric_len})"
+                }
+                button {
+                    class: "btn btn-sm btn-ghost",
+                    onclick: move |_| config.write().remove_question(idx),
                     "Del"
                 }
             }
         }
+        if rubric_open() && group_full.is_none() {
+            tr {
+                td { colspan: "8",
+                    RubricEditor { idx, config }
+                }
+            }
+        }
     }
 }
 
+// Insert a new part right after the subtree of the question
+fn add_part(mut config: Signal<Config>, idx: usize) {
+    let mut cfg = config.write();
+    let Some(parent_id) = cfg.questions.get(idx).map(|q| q.id) else {
+        return;
+    };
+    let h = Hierarchy::new(&cfg.questions);
+    let at = (idx + 1..cfg.questions.len())
+        .filter(|&i| h.has_ancestor_in(i, &[idx]))
+        .max()
+        .map_or(idx + 1, |i| i + 1);
+    let next_id = cfg.questions.iter().map(|q| q.id).max().unwrap_or(0).saturating_add(1);
+    cfg.questions.insert(at, Question {
+        id: next_id,
+        parent_id: Some(parent_id),
+        ..Default::default()
+    });
+}
+
 async fn read_questions_json_text() -> Result<Option<String>, String> {
     let handle = rfd::AsyncFileDialog::new()
         .add_filter("JSON", &["json"])
@@ -1,7 +1,8 @@
 use dioxus::prelude::*;
 use crate::Page;
+use crate::import::CsvTable;
 use crate::models::{Config, Student};
-use crate::ui::TopBar;
+use crate::ui::{CsvImportPanel, TopBar};
 
 #[component]
 pub fn MasterStudentsPage(
@@ -9,6 +10,10 @@ pub fn MasterStudentsPage(
     config: Signal<Config>,
 ) -> Element {
 
+    let mut csv_table = use_signal(|| None::<CsvTable>);
+    let mut import_seq = use_signal(|| 0usize);
+    let mut msg = use_signal(String::new);
+
     rsx! {
         div { class: "p-2 space-y-2",
 
@@ -37,8 +42,7 @@ pub fn MasterStudentsPage(
                                     if txt.trim().is_empty() {
                                         return;
                                     }
-                                    let parsed: Result<Config, _> = serde_json::from_str(&txt);
-                                    let Ok(cfg) = parsed else {
+                                    let Ok(cfg) = Config::from_json(&txt) else {
                                         return;
                                     };
                                     config.write().students = cfg.students;
@@ -46,6 +50,26 @@ pub fn MasterStudentsPage(
                             },
                             "Import JSON"
                         }
+                        button {
+                            class: "btn btn-sm",
+                            onclick: move |_| {
+                                spawn(async move {
+                                    let bytes = match read_students_csv_bytes().await {
+                                        Some(b) => b,
+                                        None => return,
+                                    };
+                                    match CsvTable::parse(&bytes) {
+                                        Ok(t) => {
+                                            import_seq += 1;
+                                            csv_table.set(Some(t));
+                                            msg.set(String::new());
+                                        }
+                                        Err(e) => msg.set(format!("CSV import failed: {e:#}")),
+                                    }
+                                });
+                            },
+                            "Import CSV"
+                        }
 
                         div { class: "flex-1" }
 
@@ -87,11 +111,21 @@ pub fn MasterStudentsPage(
                         code { "students" }
                         " will be imported; "
                         code { "questions/scores" }
-                        " are ignored."
+                        " are ignored. CSV/TSV files (UTF-8 or Shift_JIS) can be imported with column selection."
                     }
+
+                    {(!msg().is_empty()).then(|| rsx! {
+                        div { class: "alert alert-info py-2",
+                            span { class: "text-sm", "{msg}" }
+                        }
+                    })}
                 }
             }
 
+            if csv_table.read().is_some() {
+                CsvImportPanel { key: "{import_seq}", config, table: csv_table, msg }
+            }
+
             // table
             div { class: "card bg-base-100 shadow",
                 div { class: "card-body p-2",
@@ -129,16 +163,63 @@ pub fn StudentRow(
     let Some(s) = s else {
         return rsx! {};
     };
+    let duplicated = config.read().students.iter().filter(|x| x.id == s.id).count() > 1;
+
+    // id edits are committed on change and renamed into the scores
+    let mut draft = use_signal(|| None::<String>);
+    let mut clash = use_signal(|| None::<String>);
+    let id_value = draft().unwrap_or_else(|| s.id.clone());
 
     rsx! {
         tr {
             td {
                 input {
-                    class: "input input-bordered input-sm w-full",
-                    value: "{s.id}",
-                    oninput: move |ev| {
-                        let v = ev.value();
-                        config.write().students.get_mut(idx).unwrap().id = v;
+                    class: if duplicated || s.id.trim().is_empty() { "input input-bordered input-sm w-full input-error" } else { "input input-bordered input-sm w-full" },
+                    title: if duplicated { "id が重複しています" } else { "" },
+                    value: "{id_value}",
+                    oninput: move |ev| draft.set(Some(ev.value())),
+                    onchange: move |ev| {
+                        let new_id = ev.value().trim().to_string();
+                        let taken = {
+                            let cfg = config.read();
+                            let Some(cur) = cfg.students.get(idx) else {
+                                return;
+                            };
+                            if cur.id == new_id {
+                                drop(cfg);
+                                draft.set(None);
+                                return;
+                            }
+                            cfg.students.iter().enumerate().any(|(i, x)| i != idx && x.id == new_id)
+                        };
+                        if taken {
+                            clash.set(Some(new_id));
+                        } else {
+                            config.write().rename_student(idx, &new_id);
+                            draft.set(None);
+                        }
+                    }
+                }
+                if let Some(target) = clash() {
+                    div { class: "flex items-center gap-2 mt-1 text-sm",
+                        span { class: "text-warning", "\"{target}\" は既存の id です。点数を統合しますか？" }
+                        button {
+                            class: "btn btn-xs btn-warning",
+                            onclick: move |_| {
+                                config.write().merge_student(idx, &target);
+                                clash.set(None);
+                                draft.set(None);
+                            },
+                            "統合"
+                        }
+                        button {
+                            class: "btn btn-xs btn-ghost",
+                            onclick: move |_| {
+                                clash.set(None);
+                                draft.set(None);
+                            },
+                            "取消"
+                        }
                     }
                 }
             }
@@ -185,4 +266,12 @@ async fn read_students_json_text() -> Result<Option<String>, String> {
     } else {
         Ok(Some(s))
     }
-}
\ No newline at end of file
+}
+
+async fn read_students_csv_bytes() -> Option<Vec<u8>> {
+    let handle = rfd::AsyncFileDialog::new()
+        .add_filter("CSV / TSV", &["csv", "tsv", "txt"])
+        .pick_file()
+        .await?;
+    Some(handle.read().await)
+}
@@ -1,6 +1,8 @@
 use dioxus::prelude::*;
+use crate::export::{export_as, export_reliability_as, ExportFormat};
+use crate::report::export_reports;
+use crate::grading::{self, Hierarchy};
 use crate::models::{Config, TableRow};
-use std::collections::HashMap;
 
 #[component]
 pub fn MatrixTable(
@@ -8,70 +10,21 @@ pub fn MatrixTable(
 ) -> Element {
 
     // Table row
-    let mut table_rows: Signal<Vec<TableRow>> = use_signal(|| Vec::new());
+    let mut table_rows: Signal<Vec<TableRow>> = use_signal(Vec::new);
     let mut completed = use_signal(||0);
     let mut total = use_signal(||0);
+    let msg = use_signal(String::new);
+    // question ids of groups whose parts are hidden
+    let mut collapsed = use_signal(Vec::<u32>::new);
 
     use_effect(move || {
         let cfg = config.read();
-        let students = &cfg.students;
-        let questions = &cfg.questions;
+        let results = grading::evaluate(&cfg);
 
-        let score_map: HashMap<(&str, u32), Option<u32>> = cfg.scores
-            .iter()
-            .map(|s| ((s.student_id.as_str(), s.question_id), s.score))
-            .collect();
+        let rows = grading::table_rows(&cfg, &results);
 
-        let total_weight: f32 = questions.iter().map(|q| q.weight).sum();
-
-        let mut completed_student_count = 0usize;
-        let mut rows: Vec<TableRow> = Vec::with_capacity(students.len());
-
-        for student in students.iter() {
-            let mut filled = true;
-            let mut score_strings = Vec::with_capacity(questions.len());
-
-            let mut weighted_rate_sum: f32 = 0.0;
-
-            for q in questions.iter() {
-                match score_map
-                    .get(&(student.id.as_str(), q.id))
-                    .copied()
-                    .flatten()
-                {
-                    Some(scv) => {
-                        score_strings.push(scv.to_string());
-                        if q.full_score > 0 {
-                            let rate = scv as f32 / q.full_score as f32;
-                            weighted_rate_sum += rate * q.weight;
-                        } else {
-                            filled = false;
-                        }
-                    }
-                    None => {
-                        score_strings.push(String::new());
-                        filled = false;
-                    }
-                }
-            }
-
-            let final_display = if filled && total_weight > 0.0 {
-                completed_student_count += 1;
-                format!("{:.0}", weighted_rate_sum / total_weight * 100.0)
-            } else {
-                String::new()
-            };
-
-            rows.push(TableRow {
-                student_id: student.id.clone(),
-                student_name: student.name.clone(),
-                scores: score_strings,
-                final_display,
-            });
-        }
-
-        completed.set(completed_student_count);
-        total.set(students.len());
+        completed.set(grading::completed_count(&results));
+        total.set(results.len());
         table_rows.set(rows);
     });
 
@@ -81,11 +34,43 @@ pub fn MatrixTable(
                 div { class: "flex items-center gap-3",
                     div { class: "card-title", "Completed: " }
                     div { class: "text-lg", "{completed} / {total}" }
+                    div { class: "flex-1" }
+                    {(!msg().is_empty()).then(|| rsx! {
+                        span { class: "text-sm opacity-70", "{msg}" }
+                    })}
+                    button {
+                        class: "btn btn-sm",
+                        onclick: move |_| export_as(config, ExportFormat::Csv, msg),
+                        "Export CSV"
+                    }
+                    button {
+                        class: "btn btn-sm",
+                        onclick: move |_| export_as(config, ExportFormat::Tsv, msg),
+                        "Export TSV"
+                    }
+                    button {
+                        class: "btn btn-sm",
+                        title: "信頼性係数（α・SEM・項目削除時の α）",
+                        onclick: move |_| export_reliability_as(config, ExportFormat::Csv, msg),
+                        "Reliability"
+                    }
+                    button {
+                        class: "btn btn-sm",
+                        title: "受験者ごとの個別レポート（フィードバック付き）",
+                        onclick: move |_| export_reports(config, msg),
+                        "Reports"
+                    }
                 }
 
                 {
-                    // let qids = config().questions.iter().map(|q| q.id.clone()).collect::<Vec<_>>();
-                    let qnames = config().questions.iter().map(|q| q.name.clone()).collect::<Vec<_>>();
+                    let questions = config.read().questions.clone();
+                    let tree = Hierarchy::new(&questions);
+                    let collapsed_idx: Vec<usize> = (0..questions.len())
+                        .filter(|&i| collapsed.read().contains(&questions[i].id))
+                        .collect();
+                    let visible: Vec<usize> = (0..questions.len())
+                        .filter(|&i| !tree.has_ancestor_in(i, &collapsed_idx))
+                        .collect();
                     rsx! {
                         div { class: "overflow-auto max-h-96 mt-3",
                             table { class: "table table-zebra table-sm",
@@ -93,10 +78,36 @@ pub fn MatrixTable(
                                     tr {
                                         th { "id" }
                                         th { "name" }
-                                        for qname in qnames.iter() {
-                                            th { "{qname}" }
+                                        for &i in visible.iter() {
+                                            if tree.is_group(i) {
+                                                {
+                                                    let qid = questions[i].id;
+                                                    let is_collapsed = collapsed_idx.contains(&i);
+                                                    rsx! {
+                                                        th { class: "bg-base-200",
+                                                            button {
+                                                                class: "btn btn-ghost btn-xs",
+                                                                onclick: move |_| {
+                                                                    let mut c = collapsed.write();
+                                                                    if let Some(p) = c.iter().position(|&x| x == qid) {
+                                                                        c.remove(p);
+                                                                    } else {
+                                                                        c.push(qid);
+                                                                    }
+                                                                },
+                                                                if is_collapsed { "▸" } else { "▾" }
+                                                            }
+                                                            "{questions[i].name}"
+                                                        }
+                                                    }
+                                                }
+                                            } else {
+                                                th { "{questions[i].name}" }
+                                            }
                                         }
+                                        th { "adj" }
                                         th { "score" }
+                                        th { "rating" }
                                     }
                                 }
                                 tbody {
@@ -104,10 +115,20 @@ pub fn MatrixTable(
                                         tr {
                                             td { class: "font-mono", "{row.student_id}" }
                                             td { "{row.student_name}" }
-                                            for sc in row.scores.iter() {
-                                                td { class: "font-mono", "{sc}" }
+                                            for &i in visible.iter() {
+                                                td {
+                                                    class: match (tree.is_group(i), row.dropped.get(i).copied().unwrap_or(false)) {
+                                                        (_, true) => "font-mono opacity-30 line-through",
+                                                        (true, false) => "font-mono font-semibold bg-base-200",
+                                                        (false, false) => "font-mono",
+                                                    },
+                                                    title: if row.dropped.get(i).copied().unwrap_or(false) { "集計対象外" } else { "" },
+                                                    "{row.scores.get(i).cloned().unwrap_or_default()}"
+                                                }
                                             }
+                                            td { class: "text-xs text-error whitespace-nowrap", "{row.adjustment}" }
                                             td { class: "font-mono font-semibold", "{row.final_display}" }
+                                            td { "{row.rating}" }
                                         }
                                     }
                                 }
@@ -9,6 +9,17 @@ pub mod matrix_table;
 pub mod comment_panel;
 pub mod individual_panel;
 pub mod rating;
+pub mod csv_import;
+pub mod recovery_dialog;
+pub mod backup_dialog;
+pub mod validation_dialog;
+pub mod status_policy;
+pub mod rubric_editor;
+pub mod snippet_bank;
+pub mod scoring_settings;
+pub mod adjustment_panel;
+pub mod item_analysis;
+pub mod score_distribution;
 
 pub use grading::*;
 pub use master_questions::*;
@@ -20,4 +31,15 @@ pub use search::*;
 pub use matrix_table::*;
 pub use comment_panel::*;
 pub use individual_panel::*;
-pub use rating::*;
\ No newline at end of file
+pub use rating::*;
+pub use csv_import::*;
+pub use recovery_dialog::*;
+pub use backup_dialog::*;
+pub use validation_dialog::*;
+pub use status_policy::*;
+pub use rubric_editor::*;
+pub use snippet_bank::*;
+pub use scoring_settings::*;
+pub use adjustment_panel::*;
+pub use item_analysis::*;
+pub use score_distribution::*;
@@ -1,7 +1,8 @@
 use dioxus::prelude::*;
-use std::collections::HashMap;
-use crate::models::{Config, Rating};
-use crate::ui::TopBar;
+use crate::analysis::{self, Reliability};
+use crate::grading::{self, format_score, RatingStats};
+use crate::models::{Config, Rating, RatingMode, TiePolicy};
+use crate::ui::{DescriptiveStatsCard, HistogramCard, ScoringSettingsCard, StatusPolicyCard, TopBar};
 use crate::models::Page;
 
 #[component]
@@ -13,15 +14,25 @@ pub fn RatingPage(
     // ===== final スコア一覧（未入力除外） =====
     let final_scores = {
         let cfg = config.read();
-        cfg.students.iter()
-            .filter_map(|s| calc_final_percent(&cfg, &s.id)) // Option<u32>
-            .collect::<Vec<u32>>()
+        grading::final_scores(&grading::evaluate(&cfg))
     };
 
-    // ===== rating 割当結果 =====
-    let rating_stats = {
+    // ===== rating 割当結果（相対評価では閾値を分布から決める） =====
+    let (cutoffs, rating_stats) = {
         let cfg = config.read();
-        compute_rating_stats(&final_scores, &cfg.ratings)
+        let cutoffs = grading::rating_cutoffs(&cfg, &final_scores);
+        let stats = grading::compute_rating_stats(&final_scores, &cfg.ratings, &cutoffs);
+        (cutoffs, stats)
+    };
+
+    // ===== 信頼性 =====
+    let (reliability, names) = {
+        let cfg = config.read();
+        let rel = analysis::reliability(&cfg);
+        let names: Vec<String> = rel.items.iter()
+            .map(|id| cfg.questions.iter().find(|q| q.id == *id).map(|q| q.name.clone()).unwrap_or_default())
+            .collect();
+        (rel, names)
     };
 
     rsx! {
@@ -30,20 +41,38 @@ pub fn RatingPage(
             TopBar { config, on_nav }
             
             div { class: "grid grid-cols-1 xl:grid-cols-[26rem_1fr] gap-2",
-                RatingEditorCard { config }
+                RatingEditorCard { config, cutoffs }
                 RatingStatsCard { stats: rating_stats.clone() }
             }
+            div { class: "grid grid-cols-1 xl:grid-cols-[26rem_1fr] gap-2 mt-2",
+                ScoringSettingsCard { config }
+                StatusPolicyCard { config }
+            }
             div { class: "mt-2",
-                HistogramCard { scores: final_scores, ratings: config().ratings.clone() }
+                ReliabilityCard { reliability, names }
+            }
+            div { class: "grid grid-cols-1 xl:grid-cols-2 gap-2 mt-2",
+                DescriptiveStatsCard { scores: final_scores.clone() }
+                HistogramCard { scores: final_scores, config }
             }
         }
     }
 }
 
+// Effective cut-off for display; nobody reaches an infinite one
+fn cutoff_display(cutoff: f32) -> String {
+    if cutoff.is_finite() { format_score(cutoff) } else { "-".to_string() }
+}
+
 #[component]
-fn RatingEditorCard(config: Signal<Config>) -> Element {
+fn RatingEditorCard(config: Signal<Config>, cutoffs: Vec<f32>) -> Element {
 
     let ratings = config().ratings.clone();
+    let (mode, tie_policy) = {
+        let cfg = config.read();
+        (cfg.rating_mode, cfg.tie_policy)
+    };
+    let relative = mode == RatingMode::Relative;
 
     rsx! {
         div { class: "card bg-base-100 shadow",
@@ -51,6 +80,39 @@ fn RatingEditorCard(config: Signal<Config>) -> Element {
 
                 div { class: "card-title", "区分・閾値設定" }
 
+                div { class: "flex flex-wrap gap-2 items-center",
+                    select {
+                        class: "select select-sm select-bordered",
+                        onchange: move |e| {
+                            config.write().rating_mode = match e.value().as_str() {
+                                "relative" => RatingMode::Relative,
+                                _ => RatingMode::Absolute,
+                            };
+                        },
+                        option { value: "absolute", selected: !relative, "絶対評価（点数で区切る）" }
+                        option { value: "relative", selected: relative, "相対評価（人数の上限）" }
+                    }
+                    if relative {
+                        select {
+                            class: "select select-sm select-bordered",
+                            title: "上限の境目で同点が並んだとき",
+                            onchange: move |e| {
+                                config.write().tie_policy = match e.value().as_str() {
+                                    "up" => TiePolicy::Up,
+                                    _ => TiePolicy::Down,
+                                };
+                            },
+                            option { value: "down", selected: tie_policy == TiePolicy::Down, "同点は下位へ（上限を守る）" }
+                            option { value: "up", selected: tie_policy == TiePolicy::Up, "同点は上位へ（上限を超えうる）" }
+                        }
+                    }
+                }
+                if relative {
+                    p { class: "text-xs opacity-60",
+                        "上位の区分から順に、上限 % までの人数で閾値を決めます。点数は下限として残ります。上限が空欄の区分は点数だけで区切ります。"
+                    }
+                }
+
                 {
                     ratings.iter().enumerate().map(|(i, r)| {
                         rsx! {
@@ -76,13 +138,35 @@ fn RatingEditorCard(config: Signal<Config>) -> Element {
                                         if let Ok(v) = e.value().parse::<u32>() {
                                             let mut cfg = config.read().clone();
                                             cfg.ratings[i].min_score = v.min(100);
-                                            cfg.ratings.sort_by(|a,b| b.min_score.cmp(&a.min_score));
+                                            cfg.ratings.sort_by_key(|r| std::cmp::Reverse(r.min_score));
                                             config.set(cfg);
                                         }
                                     }
                                 }
 
                                 span { class: "text-sm opacity-60", "以上" }
+
+                                if relative {
+                                    input {
+                                        class: "input input-sm input-bordered w-20",
+                                        r#type: "number",
+                                        min: "0",
+                                        max: "100",
+                                        step: "0.1",
+                                        placeholder: "-",
+                                        title: "この区分の人数の上限 (%)",
+                                        value: r.quota.map(|q| q.to_string()).unwrap_or_default(),
+                                        oninput: move |e| {
+                                            let v = e.value();
+                                            let quota = v.trim().parse::<f32>().ok().map(|q| q.clamp(0.0, 100.0));
+                                            if v.trim().is_empty() || quota.is_some() {
+                                                config.write().ratings[i].quota = quota;
+                                            }
+                                        }
+                                    }
+                                    span { class: "text-sm opacity-60", "% まで" }
+                                    span { class: "text-sm font-mono", "→ {cutoff_display(cutoffs.get(i).copied().unwrap_or(f32::INFINITY))}" }
+                                }
                             }
                         }
                     })
@@ -95,6 +179,7 @@ fn RatingEditorCard(config: Signal<Config>) -> Element {
                         cfg.ratings.push(Rating {
                             label: "New".to_string(),
                             min_score: 0,
+                            quota: None,
                         });
                         config.set(cfg);
                     },
@@ -116,6 +201,7 @@ fn RatingStatsCard(stats: Vec<RatingStats>) -> Element {
                     thead {
                         tr {
                             th { "Rating" }
+                            th { "閾値" }
                             th { "人数" }
                             th { "割合" }
                         }
@@ -125,6 +211,7 @@ fn RatingStatsCard(stats: Vec<RatingStats>) -> Element {
                             stats.iter().map(|s| rsx! {
                                 tr {
                                     td { "{s.label}" }
+                                    td { "{cutoff_display(s.cutoff)}" }
                                     td { "{s.count}" }
                                     td { "{s.ratio * 100.0}%" }
                                 }
@@ -138,115 +225,65 @@ fn RatingStatsCard(stats: Vec<RatingStats>) -> Element {
 }
 
 #[component]
-fn HistogramCard(
-    scores: Vec<u32>,
-    ratings: Vec<Rating>,
+fn ReliabilityCard(
+    reliability: Reliability,
+    names: Vec<String>,
 ) -> Element {
 
-    let bins = histogram(&scores, 5); // 5点刻み
-
-    let max = bins.iter().copied().max().unwrap_or(1);
+    let rel = &reliability;
+    let fixed = |v: Option<f32>| v.map(|v| format!("{v:.3}")).unwrap_or_else(|| "-".to_string());
+    let alpha = fixed(rel.alpha);
+    let sem = fixed(rel.sem);
+    let k = rel.items.len();
 
     rsx! {
         div { class: "card bg-base-100 shadow",
             div { class: "card-body",
-                div { class: "card-title", "ヒストグラム（5点刻み）" }
+                div { class: "card-title", "信頼性" }
 
-                div { class: "flex items-end gap-1 h-40",
+                div { class: "flex flex-wrap gap-6",
+                    div {
+                        div { class: "text-sm opacity-60", "Cronbach の α" }
+                        div { class: "text-2xl font-mono", "{alpha}" }
+                    }
+                    div {
+                        div { class: "text-sm opacity-60", "測定の標準誤差 (SEM)" }
+                        div { class: "text-2xl font-mono", "{sem}" }
+                        div { class: "text-xs opacity-60", "/ {rel.full_score} 点" }
+                    }
+                    div {
+                        div { class: "text-sm opacity-60", "対象" }
+                        div { class: "text-2xl font-mono", "{rel.n} 人 × {k} 問" }
+                        div { class: "text-xs opacity-60", "全問入力済みの受験者・大問単位（加点問題を除く）" }
+                        if rel.excluded > 0 {
+                            div { class: "text-xs text-warning", "除外の問題がある {rel.excluded} 人は対象外" }
+                        }
+                    }
+                }
 
-                    {
-                        bins.iter().enumerate().map(|(_i, &count)| {
-                            let height = (count as f32 / max as f32) * 100.0;
-                            rsx! {
-                                div {
-                                    class: "bg-primary w-3",
-                                    style: "height: {height}%;"
+                if k >= 3 {
+                    table { class: "table table-sm",
+                        thead {
+                            tr {
+                                th { "問題" }
+                                th { class: "text-right", "除外時の α" }
+                            }
+                        }
+                        tbody {
+                            for (name, a) in names.iter().zip(rel.alpha_if_deleted.iter().copied()) {
+                                tr {
+                                    td { "{name}" }
+                                    td {
+                                        // leaving the question out would make the exam more consistent
+                                        class: if a.zip(rel.alpha).is_some_and(|(a, all)| a > all) { "text-right font-mono text-warning" } else { "text-right font-mono" },
+                                        "{fixed(a)}"
+                                    }
                                 }
                             }
-                        })
+                        }
                     }
                 }
             }
         }
     }
 }
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct RatingStats {
-    pub label: String,
-    pub count: usize,
-    pub ratio: f32,
-}
-
-fn compute_rating_stats(
-    scores: &[u32],
-    ratings: &[Rating],
-) -> Vec<RatingStats> {
-
-    let mut counts = vec![0usize; ratings.len()];
-
-    for &s in scores {
-        if let Some((i, _)) = ratings.iter()
-            .enumerate()
-            .find(|(_, r)| s >= r.min_score)
-        {
-            counts[i] += 1;
-        }
-    }
-
-    let total = scores.len().max(1);
-
-    ratings.iter().enumerate().map(|(i, r)| {
-        RatingStats {
-            label: r.label.clone(),
-            count: counts[i],
-            ratio: counts[i] as f32 / total as f32,
-        }
-    }).collect()
-}
-
-fn calc_final_percent( config: &Config, student_id: &str) -> Option<u32> {
-    // let cfg = config;
-    let questions = &config.questions;
-
-    let score_map: HashMap<(&str, u32), Option<u32>> = config.scores
-        .iter()
-        .map(|s| ((s.student_id.as_str(), s.question_id), s.score))
-        .collect();
-
-    let total_weight: f32 = questions.iter().map(|q| q.weight).sum();
-    let mut weighted_rate_sum: f32 = 0.0;
-
-    for q in questions.iter() {
-        match score_map
-            .get(&(student_id, q.id))
-            .copied()
-            .flatten()
-        {
-            Some(scv) => {
-                if q.full_score > 0 {
-                    let rate = scv as f32 / q.full_score as f32;
-                    weighted_rate_sum += rate * q.weight;
-                } else {
-                    return None; // full_score=0 は未入力扱い
-                }
-            }
-            None => return None, // 未入力
-        }
-    }
-    if total_weight > 0.0 {
-        Some((weighted_rate_sum / total_weight * 100.0) as u32)
-    } else {
-        None
-    }
-}
-
-fn histogram(scores: &[u32], bin_width: u32) -> Vec<usize> {
-    let bins = (100 / bin_width) + 1; // 例: bin_width=5 => 21
-    let mut h = vec![0usize; bins as usize];
-    for &s in scores {
-        let idx = (s / bin_width).min(bins - 1) as usize;
-        h[idx] += 1;
-    }
-    h
-}
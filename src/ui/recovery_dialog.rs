@@ -0,0 +1,56 @@
+use dioxus::prelude::*;
+use std::time::SystemTime;
+use crate::autosave::{self, Recovery};
+use crate::models::Config;
+
+#[component]
+pub fn RecoveryDialog(
+    recovery: Signal<Option<Recovery>>,
+    config: Signal<Config>,
+) -> Element {
+
+    let Some(r) = recovery() else {
+        return rsx! {};
+    };
+
+    let minutes = SystemTime::now()
+        .duration_since(r.modified)
+        .map(|d| d.as_secs() / 60)
+        .unwrap_or(0);
+    let path = r.path.to_string_lossy().to_string();
+    let target = r.config.save_path.clone().unwrap_or_else(|| "（未保存）".to_string());
+
+    rsx! {
+        div { class: "modal modal-open",
+            div { class: "modal-box",
+                h3 { class: "font-bold text-lg", "未保存の作業内容があります" }
+                p { class: "mt-3 text-sm",
+                    "前回の終了時に保存されていない変更が {minutes} 分前に自動保存されています。復元しますか？"
+                }
+                div { class: "mt-2 text-xs opacity-70 font-mono break-all", "{target}" }
+                div { class: "text-xs opacity-50 font-mono break-all", "{path}" }
+
+                div { class: "modal-action",
+                    button {
+                        class: "btn btn-sm btn-ghost",
+                        onclick: move |_| {
+                            if let Some(r) = recovery.take() {
+                                spawn(async move { autosave::clear_recovery(&r.config).await });
+                            }
+                        },
+                        "破棄"
+                    }
+                    button {
+                        class: "btn btn-sm btn-primary",
+                        onclick: move |_| {
+                            if let Some(r) = recovery.take() {
+                                config.set(r.config);
+                            }
+                        },
+                        "復元"
+                    }
+                }
+            }
+        }
+    }
+}
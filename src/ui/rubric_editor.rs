@@ -0,0 +1,107 @@
+use dioxus::prelude::*;
+use crate::grading::format_score;
+use crate::models::{Config, RubricItem};
+
+// Rubric items of one question; scored cells follow point changes
+#[component]
+pub fn RubricEditor(
+    idx: usize,
+    config: Signal<Config>,
+) -> Element {
+
+    let Some(q) = config.read().questions.get(idx).cloned() else {
+        return rsx! {};
+    };
+    let max_points: f32 = q.rubric.iter().filter(|r| !r.deduction).map(|r| r.points).sum();
+    let max_display = format_score(max_points);
+
+    rsx! {
+        div { class: "flex flex-col gap-1 p-2 rounded bg-base-200",
+            for (i, item) in q.rubric.iter().cloned().enumerate() {
+                div { key: "{item.id}", class: "flex items-center gap-2",
+                    input {
+                        class: "input input-bordered input-sm flex-1",
+                        placeholder: "採点基準",
+                        value: "{item.criterion}",
+                        oninput: move |ev| {
+                            if let Some(item) = config.write().questions.get_mut(idx).and_then(|q| q.rubric.get_mut(i)) {
+                                item.criterion = ev.value();
+                            }
+                        }
+                    }
+                    input {
+                        class: "input input-bordered input-sm w-24",
+                        r#type: "number",
+                        step: "0.5",
+                        min: "0",
+                        value: "{item.points}",
+                        onchange: move |ev| {
+                            let v = ev.value().parse::<f32>().unwrap_or(0.0).max(0.0);
+                            edit_item(config, idx, i, |item| item.points = v);
+                        }
+                    }
+                    label { class: "flex items-center gap-1 text-sm",
+                        input {
+                            r#type: "checkbox",
+                            class: "checkbox checkbox-sm",
+                            checked: item.deduction,
+                            onchange: move |ev| {
+                                let v = ev.checked();
+                                edit_item(config, idx, i, |item| item.deduction = v);
+                            }
+                        }
+                        "減点"
+                    }
+                    button {
+                        class: "btn btn-sm btn-ghost",
+                        onclick: move |_| {
+                            let mut cfg = config.write();
+                            let Some(q) = cfg.questions.get_mut(idx) else {
+                                return;
+                            };
+                            if i < q.rubric.len() {
+                                q.rubric.remove(i);
+                            }
+                            let question = q.clone();
+                            cfg.scores.rescore(&question);
+                        },
+                        "Del"
+                    }
+                }
+            }
+            div { class: "flex items-center gap-2",
+                button {
+                    class: "btn btn-xs",
+                    onclick: move |_| {
+                        if let Some(q) = config.write().questions.get_mut(idx) {
+                            let next_id = q.rubric.iter().map(|r| r.id).max().unwrap_or(0).saturating_add(1);
+                            q.rubric.push(RubricItem {
+                                id: next_id,
+                                criterion: String::new(),
+                                points: 1.0,
+                                deduction: false,
+                            });
+                        }
+                    },
+                    "基準を追加"
+                }
+                if max_points > q.full_score as f32 {
+                    span { class: "text-xs text-warning",
+                        "加点の合計 {max_display} が満点 {q.full_score} を超えています（満点で打ち切り）"
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Edit an item and re-derive the scores of the question
+fn edit_item(mut config: Signal<Config>, idx: usize, i: usize, f: impl FnOnce(&mut RubricItem)) {
+    let mut cfg = config.write();
+    let Some(item) = cfg.questions.get_mut(idx).and_then(|q| q.rubric.get_mut(i)) else {
+        return;
+    };
+    f(item);
+    let question = cfg.questions[idx].clone();
+    cfg.scores.rescore(&question);
+}
@@ -0,0 +1,362 @@
+use std::rc::Rc;
+use dioxus::prelude::*;
+use crate::analysis::{self, Descriptive};
+use crate::grading::{self, format_score};
+use crate::models::{Config, Rating, RatingMode};
+
+// Drawing area shared by the histogram and the box plot (SVG user units)
+const WIDTH: f32 = 600.0;
+const LEFT: f32 = 40.0;
+const RIGHT: f32 = 12.0;
+const PLOT_W: f32 = WIDTH - LEFT - RIGHT;
+
+const BIN_WIDTHS: [u32; 6] = [1, 2, 5, 10, 20, 25];
+
+// band colors, from the highest rating down
+const BAND_FILLS: [&str; 5] = ["fill-success", "fill-info", "fill-primary", "fill-warning", "fill-secondary"];
+const BAND_STROKES: [&str; 5] = ["stroke-success", "stroke-info", "stroke-primary", "stroke-warning", "stroke-secondary"];
+
+// Color slot of each rating: rank by min_score, highest first
+fn band_ranks(ratings: &[Rating]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..ratings.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(ratings[i].min_score));
+    let mut ranks = vec![0; ratings.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        ranks[i] = rank % BAND_FILLS.len();
+    }
+    ranks
+}
+
+// x coordinate of a score on a 0..upper axis
+fn x_of(score: f32, upper: f32) -> f32 {
+    LEFT + score.clamp(0.0, upper) / upper * PLOT_W
+}
+
+// Upper end of the score axis: 100, or more when bonus points go past it
+fn axis_upper(scores: &[f32], bin_width: f32) -> f32 {
+    let max = scores.iter().copied().fold(100.0f32, f32::max);
+    (max / bin_width).ceil().max(1.0) * bin_width
+}
+
+// 平均・中央値・四分位 + 箱ひげ図
+#[component]
+pub fn DescriptiveStatsCard(scores: Vec<f32>) -> Element {
+
+    let Some(d) = analysis::describe(&scores) else {
+        return rsx! {
+            div { class: "card bg-base-100 shadow",
+                div { class: "card-body",
+                    div { class: "card-title", "基本統計量" }
+                    p { class: "text-sm opacity-60", "集計対象の受験者がいません" }
+                }
+            }
+        };
+    };
+    let modes = if d.modes.is_empty() {
+        "-".to_string()
+    } else {
+        d.modes.iter().map(|m| format_score(*m)).collect::<Vec<_>>().join(", ")
+    };
+    let fixed = |v: f32| format!("{v:.2}");
+    let items = [
+        ("人数", d.n.to_string()),
+        ("平均", fixed(d.mean)),
+        ("中央値", format_score(d.median)),
+        ("最頻値", modes),
+        ("標準偏差", fixed(d.sd)),
+        ("最小", format_score(d.min)),
+        ("最大", format_score(d.max)),
+        ("第1四分位", format_score(d.q1)),
+        ("第3四分位", format_score(d.q3)),
+        ("四分位範囲", format_score(d.iqr())),
+    ];
+    let upper = axis_upper(&scores, 10.0);
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body",
+                div { class: "card-title", "基本統計量" }
+
+                div { class: "grid grid-cols-2 md:grid-cols-5 gap-2",
+                    for (label, value) in items.into_iter() {
+                        div {
+                            div { class: "text-xs opacity-60", "{label}" }
+                            div { class: "font-mono", "{value}" }
+                        }
+                    }
+                }
+
+                BoxPlot { stats: d, upper }
+            }
+        }
+    }
+}
+
+#[component]
+fn BoxPlot(stats: Descriptive, upper: f32) -> Element {
+    let d = &stats;
+    let (x_min, x_q1, x_med, x_q3, x_max) =
+        (x_of(d.min, upper), x_of(d.q1, upper), x_of(d.median, upper), x_of(d.q3, upper), x_of(d.max, upper));
+    let x_mean = x_of(d.mean, upper);
+    let box_w = (x_q3 - x_q1).max(1.0);
+    let ticks: Vec<f32> = (0..=(upper / 10.0) as u32).map(|t| t as f32 * 10.0).collect();
+
+    rsx! {
+        svg {
+            class: "w-full text-base-content",
+            view_box: "0 0 {WIDTH} 64",
+            // whiskers: min to max
+            line { x1: "{x_min}", y1: "20", x2: "{x_q1}", y2: "20", stroke: "currentColor" }
+            line { x1: "{x_q3}", y1: "20", x2: "{x_max}", y2: "20", stroke: "currentColor" }
+            line { x1: "{x_min}", y1: "12", x2: "{x_min}", y2: "28", stroke: "currentColor" }
+            line { x1: "{x_max}", y1: "12", x2: "{x_max}", y2: "28", stroke: "currentColor" }
+            rect {
+                x: "{x_q1}", y: "6", width: "{box_w}", height: "28",
+                class: "fill-primary/30 stroke-primary",
+            }
+            line { x1: "{x_med}", y1: "6", x2: "{x_med}", y2: "34", class: "stroke-primary", stroke_width: "2" }
+            // mean
+            circle { cx: "{x_mean}", cy: "20", r: "3", class: "fill-secondary" }
+
+            line { x1: "{LEFT}", y1: "42", x2: "{LEFT + PLOT_W}", y2: "42", stroke: "currentColor", stroke_opacity: "0.4" }
+            for t in ticks.into_iter() {
+                text {
+                    x: "{x_of(t, upper)}", y: "56",
+                    text_anchor: "middle", font_size: "10", fill: "currentColor",
+                    "{t}"
+                }
+            }
+        }
+    }
+}
+
+// 得点分布; in absolute mode the rating thresholds can be dragged along the score axis
+#[component]
+pub fn HistogramCard(
+    scores: Vec<f32>,
+    config: Signal<Config>,
+) -> Element {
+
+    let mut bin_width = use_signal(|| 5u32);
+    let mut hovered = use_signal(|| None::<usize>);
+    // rating being dragged, and the on-screen (left, width) of the chart
+    let mut dragging = use_signal(|| None::<usize>);
+    let mut chart = use_signal(|| None::<Rc<MountedData>>);
+    let mut chart_rect = use_signal(|| None::<(f64, f64)>);
+
+    let (ratings, cutoffs, relative) = {
+        let cfg = config.read();
+        (cfg.ratings.clone(), grading::rating_cutoffs(&cfg, &scores), cfg.rating_mode == RatingMode::Relative)
+    };
+    let ranks = band_ranks(&ratings);
+
+    let w = bin_width() as f32;
+    let bins = analysis::histogram(&scores, w);
+    let upper = axis_upper(&scores, w);
+    let max = bins.iter().copied().max().unwrap_or(0).max(1);
+
+    // drawing area
+    let (top, plot_h) = (18.0f32, 180.0f32);
+    let bottom = top + plot_h;
+    let y_of = |count: usize| bottom - count as f32 / max as f32 * plot_h;
+    let y_step = max.div_ceil(5).max(1);
+    let y_ticks: Vec<usize> = (0..=max).step_by(y_step).collect();
+    let x_step = bin_width().max(10) as f32;
+    let x_ticks: Vec<f32> = (0..=(upper / x_step) as u32).map(|t| t as f32 * x_step).collect();
+    let bar_w = (PLOT_W / bins.len() as f32 - 1.0).max(1.0);
+
+    // bar color: the band its lower edge falls into
+    let bar_fill = |i: usize| match grading::assign_rating(i as f32 * w, &ratings, &cutoffs) {
+        Some(r) => BAND_FILLS[ranks[r]],
+        None => "fill-base-content/30",
+    };
+    // bands: from each threshold up to the next higher one
+    let bands: Vec<(f32, f32, usize)> = cutoffs.iter().enumerate()
+        .map(|(i, &lo)| {
+            let hi = cutoffs.iter().copied().filter(|&m| m > lo).fold(upper, f32::min);
+            (lo, hi, ranks[i])
+        })
+        .filter(|(lo, _, _)| *lo < upper)
+        .collect();
+    // thresholds drawn on the chart: (rating, cut-off)
+    let lines: Vec<(usize, f32)> = cutoffs.iter().copied().enumerate()
+        .filter(|(_, c)| *c <= upper)
+        .collect();
+
+    // end of a drag: keep the list in the order RatingEditorCard uses
+    let mut drop_threshold = move || {
+        if dragging().is_some() {
+            dragging.set(None);
+            config.write().ratings.sort_by_key(|r| std::cmp::Reverse(r.min_score));
+        }
+    };
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body",
+                div { class: "flex items-center gap-2",
+                    div { class: "card-title", "ヒストグラム" }
+                    div { class: "flex-1" }
+                    span { class: "text-sm opacity-60", "階級幅" }
+                    select {
+                        class: "select select-sm select-bordered",
+                        onchange: move |e| {
+                            if let Ok(v) = e.value().parse::<u32>() {
+                                bin_width.set(v);
+                                hovered.set(None);
+                            }
+                        },
+                        for bw in BIN_WIDTHS {
+                            option { value: "{bw}", selected: bin_width() == bw, "{bw} 点" }
+                        }
+                    }
+                }
+
+                div {
+                    class: if dragging().is_some() { "cursor-ew-resize select-none" } else { "" },
+                    onmounted: move |e| chart.set(Some(e.data())),
+                    onmousemove: move |e| {
+                        let (Some(i), Some((left, width))) = (dragging(), chart_rect()) else {
+                            return;
+                        };
+                        let x = ((e.client_coordinates().x - left) / width) as f32 * WIDTH;
+                        let score = ((x - LEFT) / PLOT_W * upper).round().clamp(0.0, 100.0) as u32;
+                        if let Some(r) = config.write().ratings.get_mut(i).filter(|r| r.min_score != score) {
+                            r.min_score = score;
+                        }
+                    },
+                    onmouseup: move |_| drop_threshold(),
+                    onmouseleave: move |_| drop_threshold(),
+
+                    svg {
+                        class: "w-full text-base-content",
+                        view_box: "0 0 {WIDTH} 230",
+
+                        // rating bands
+                        for (lo, hi, rank) in bands.into_iter() {
+                            rect {
+                                x: "{x_of(lo, upper)}", y: "{top}",
+                                width: "{x_of(hi, upper) - x_of(lo, upper)}", height: "{plot_h}",
+                                class: BAND_FILLS[rank], fill_opacity: "0.08", pointer_events: "none",
+                            }
+                        }
+
+                        // y axis and grid
+                        for t in y_ticks.into_iter() {
+                            line {
+                                x1: "{LEFT}", y1: "{y_of(t)}", x2: "{LEFT + PLOT_W}", y2: "{y_of(t)}",
+                                stroke: "currentColor", stroke_opacity: "0.1",
+                            }
+                            text {
+                                x: "{LEFT - 4.0}", y: "{y_of(t)}",
+                                text_anchor: "end", dominant_baseline: "middle", font_size: "10", fill: "currentColor",
+                                "{t}"
+                            }
+                        }
+                        text {
+                            x: "10", y: "{top + plot_h / 2.0}",
+                            text_anchor: "middle", font_size: "11", fill: "currentColor",
+                            transform: "rotate(-90 10 {top + plot_h / 2.0})",
+                            "人数"
+                        }
+
+                        // bars
+                        for (i, count) in bins.iter().copied().enumerate() {
+                            rect {
+                                key: "{i}",
+                                x: "{x_of(i as f32 * w, upper) + 0.5}",
+                                y: "{y_of(count)}",
+                                width: "{bar_w}",
+                                height: "{bottom - y_of(count)}",
+                                class: bar_fill(i),
+                                fill_opacity: if hovered() == Some(i) { "1" } else { "0.7" },
+                            }
+                        }
+                        // hover targets span the full height, so empty bins show their count too
+                        for i in 0..bins.len() {
+                            rect {
+                                key: "hit-{i}",
+                                x: "{x_of(i as f32 * w, upper)}",
+                                y: "{top}",
+                                width: "{PLOT_W / bins.len() as f32}",
+                                height: "{plot_h}",
+                                fill: "transparent",
+                                onmouseenter: move |_| hovered.set(Some(i)),
+                                onmouseleave: move |_| hovered.set(None),
+                            }
+                        }
+
+                        // rating thresholds; the wide transparent line is the drag handle
+                        for (i, cut) in lines.into_iter() {
+                            line {
+                                x1: "{x_of(cut, upper)}", y1: "{top - 6.0}",
+                                x2: "{x_of(cut, upper)}", y2: "{bottom}",
+                                class: BAND_STROKES[ranks[i]],
+                                stroke_width: if dragging() == Some(i) { "2" } else { "1.5" },
+                                stroke_dasharray: "4 3", pointer_events: "none",
+                            }
+                            text {
+                                x: "{x_of(cut, upper) + 3.0}", y: "{top - 8.0}",
+                                font_size: "10", class: BAND_FILLS[ranks[i]], pointer_events: "none",
+                                "{ratings[i].label} ≥ {format_score(cut)}"
+                            }
+                            // relative cut-offs follow the distribution, so only absolute ones move
+                            if !relative {
+                                line {
+                                    x1: "{x_of(cut, upper)}", y1: "{top - 6.0}",
+                                    x2: "{x_of(cut, upper)}", y2: "{bottom}",
+                                    stroke: "transparent", stroke_width: "10", class: "cursor-ew-resize",
+                                    onmousedown: move |e| {
+                                        e.prevent_default();
+                                        hovered.set(None);
+                                        dragging.set(Some(i));
+                                        // measure the chart once per drag; it does not move meanwhile
+                                        spawn(async move {
+                                            let Some(el) = chart() else {
+                                                return;
+                                            };
+                                            if let Ok(rect) = el.get_client_rect().await {
+                                                chart_rect.set(Some((rect.origin.x, rect.size.width)));
+                                            }
+                                        });
+                                    },
+                                }
+                            }
+                        }
+
+                        // x axis
+                        line { x1: "{LEFT}", y1: "{bottom}", x2: "{LEFT + PLOT_W}", y2: "{bottom}", stroke: "currentColor" }
+                        for t in x_ticks.into_iter() {
+                            text {
+                                x: "{x_of(t, upper)}", y: "{bottom + 12.0}",
+                                text_anchor: "middle", font_size: "10", fill: "currentColor",
+                                "{t}"
+                            }
+                        }
+                        text {
+                            x: "{LEFT + PLOT_W / 2.0}", y: "{bottom + 28.0}",
+                            text_anchor: "middle", font_size: "11", fill: "currentColor",
+                            "得点"
+                        }
+
+                        // count of the hovered bin
+                        if let Some(i) = hovered().filter(|&i| i < bins.len() && dragging().is_none()) {
+                            text {
+                                x: "{x_of((i as f32 + 0.5) * w, upper)}", y: "{y_of(bins[i]) - 4.0}",
+                                text_anchor: "middle", font_size: "11", font_weight: "bold", fill: "currentColor",
+                                pointer_events: "none",
+                                "{bins[i]} 人"
+                            }
+                        }
+                    }
+                }
+
+                if let Some(i) = hovered().filter(|&i| i < bins.len() && dragging().is_none()) {
+                    div { class: "text-sm opacity-70",
+                        "{format_score(i as f32 * w)} – {format_score((i + 1) as f32 * w)} 点: {bins[i]} 人"
+                    }
+                }
+            }
+        }
+    }
+}
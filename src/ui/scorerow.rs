@@ -1,5 +1,7 @@
 use dioxus::prelude::*;
-use crate::models::{Config, Question, Score};
+use crate::grading::format_score;
+use crate::models::{Config, Question, ScoreStatus};
+use crate::ui::keep_out_of_hotkeys;
 
 #[component]
 pub fn ScoreRow(
@@ -7,86 +9,147 @@ pub fn ScoreRow(
     cur_question_id: Signal<Option<u32>>,
     cur_student_idx: ReadSignal<usize>,
     qidx: usize,
+    // nesting level of a part, for indentation
+    depth: usize,
+    // left out by a best-N / N-chosen rule for this student
+    dropped: bool,
     config: Signal<Config>,
     is_focused: bool,
     move_to_next: EventHandler<()>,
     move_to_prev: EventHandler<()>,
 ) -> Element {
 
-    let student_id = config().students.get(cur_student_idx()).map(|s| s.id.to_string()).unwrap_or_default();
-    let question = config().questions.iter().find(|q| q.id == question_id).cloned().unwrap_or(Question {
-        id: 0,
+    let cfg = config.read();
+    let student_id = cfg.students.get(cur_student_idx()).map(|s| s.id.to_string()).unwrap_or_default();
+    let question = cfg.questions.iter().find(|q| q.id == question_id).cloned().unwrap_or(Question {
         name: "Unknown".to_string(),
         full_score: 100,
-        weight: 1.0,
-        comment: "".to_string(),
+        ..Default::default()
     });
     let question_id = question.id;
-    let q_name = question.name;
+    let q_name = question.name.clone();
     let full = question.full_score;
+    let min = question.min_score.min(0.0);
+    // negative marking needs the minus key for input
+    let allows_negative = min < 0.0;
+    let step = format_score(question.step);
+    let stored_value = cfg.scores.value(&student_id, question_id);
+    let stored = stored_value.map(format_score).unwrap_or_default();
+    // above full marks is kept, but flagged
+    let over_full = stored_value.is_some_and(|v| question.over_full(v));
+    let status = cfg.scores.status(&student_id, question_id);
+    let ticked = cfg.scores.ticked(&student_id, question_id).map(|t| t.to_vec());
+    let (note, feedback) = cfg.scores.get(&student_id, question_id)
+        .map(|sc| (sc.note.clone(), sc.feedback.clone()))
+        .unwrap_or_default();
+    drop(cfg);
+    let mut comments_open = use_signal(|| false);
+    let has_comments = !note.is_empty() || !feedback.is_empty();
+    let note_student_id = student_id.clone();
+    let feedback_student_id = student_id.clone();
+    let key_student_id = student_id.clone();
+    let snippet_question = question.clone();
+    // shown as inserted, even if the snippet was edited since
+    let used_snippets: Vec<(u32, String)> = config.read().scores.get(&student_id, question_id)
+        .map(|sc| sc.snippets.iter().map(|u| (u.id, u.text.clone())).collect())
+        .unwrap_or_default();
+    // rubric questions are scored by ticking items only
+    let rubric = (!question.rubric.is_empty()).then(|| question.clone());
+    let is_rubric = rubric.is_some();
+
+    // text that is not a valid score stays visible (the cell itself is cleared)
+    let mut draft = use_signal(|| None::<(String, String)>);
+    let invalid = draft().filter(|(sid, _)| *sid == student_id).map(|(_, text)| text);
+    let value = invalid.clone().unwrap_or(stored);
 
     rsx! {
-        div { class: "grid grid-cols-[1fr_auto_auto] md:grid-cols-[8rem_6rem_auto_auto] gap-2 items-center",
-            div { class: "font-semibold truncate", "{q_name}" }
+        div {
+            class: if dropped {
+                "grid grid-cols-[1fr_auto_auto] md:grid-cols-[8rem_6rem_auto_auto] gap-2 items-center opacity-40"
+            } else {
+                "grid grid-cols-[1fr_auto_auto] md:grid-cols-[8rem_6rem_auto_auto] gap-2 items-center"
+            },
+            title: if dropped { "集計対象外" } else { "" },
+            div { class: "font-semibold truncate", style: "padding-left: {depth}rem;", "{q_name}" }
             input {
                 id: "score-{qidx}",
                 r#type: "number",
-                value: config().scores
-                    .iter()
-                    .find(|sc| sc.question_id == question_id && sc.student_id == student_id )
-                    .map(|sc| if let Some(scv) = sc.score { scv.to_string() } else { String::new() }),
-                min: 0,
-                max: full,
+                value: "{value}",
+                placeholder: status.map(|st| st.label()).unwrap_or_default(),
+                min: "{min}",
+                step: "{step}",
+                readonly: is_rubric,
                 required: true,
-                class: "input validator",
+                class: if invalid.is_some() {
+                    "input validator input-error"
+                } else if over_full {
+                    "input validator input-warning"
+                } else {
+                    "input validator"
+                },
                 autofocus: is_focused,
 
                 oninput: move |e| {
-                    let mut s = e.value();
-                    s.retain(|c| c.is_ascii_digit());
-                    let mut binding = config.write();
-                    let score_opt = binding.scores
-                        .iter_mut()
-                        .find(|sc| sc.question_id == question_id && sc.student_id == student_id);
-                    if let Some(score) = score_opt {
-                        if let Ok(num) = s.parse() {
-                            if num <= full {
-                                score.score = Some(num); 
-                            } else {
-                                score.score = None;
-                            }
-                        } else {
-                            score.score = None;
-                        }
+                    let mut s = e.value().replace(',', ".");
+                    s.retain(|c| c.is_ascii_digit() || c == '.' || c == '-');
+                    let score = s.parse::<f32>().ok().filter(|&v| question.accepts(v));
+                    if score.is_some() || s.is_empty() {
+                        draft.set(None);
                     } else {
-                        let score;
-                        if let Ok(num) = s.parse() { 
-                            if num <= full {
-                                score = Some(num);
-                            } else {
-                                score = None;
-                            }
-                        } else { 
-                            score = None; 
-                        };
-                        binding.scores.push(Score {
-                            student_id: student_id.clone(),
-                            question_id,
-                            score,
-                        });
+                        draft.set(Some((student_id.clone(), s)));
                     }
+                    config.write().scores.set(&student_id, question_id, score);
                 },
 
+                // A = 欠席, E = 免除, N = 未提出; Backspace / Delete clears the code
+                // Alt+1..9 inserts the snippet with that number
                 onkeydown: move |e| {
+                    let mods = e.modifiers();
+                    if mods.contains(Modifiers::ALT) {
+                        if let Some(n) = snippet_number(e.code()) {
+                            e.prevent_default();
+                            if let Some(sn) = snippet_question.snippets.get(n) {
+                                draft.set(None);
+                                config.write().scores.use_snippet(&key_student_id, &snippet_question, sn.id);
+                            }
+                        }
+                        return;
+                    }
+                    if mods.contains(Modifiers::CONTROL) || mods.contains(Modifiers::META) {
+                        return;
+                    }
+                    let code = match e.code() {
+                        Code::KeyA => Some(ScoreStatus::Absent),
+                        Code::KeyE => Some(ScoreStatus::Exempt),
+                        Code::KeyN => Some(ScoreStatus::NotSubmitted),
+                        _ => None,
+                    };
+                    if let Some(code) = code {
+                        e.prevent_default();
+                        draft.set(None);
+                        config.write().scores.set_status(&key_student_id, question_id, Some(code));
+                        move_to_next.call(());
+                        return;
+                    }
                     match e.code() {
+                        Code::Backspace | Code::Delete if status.is_some() => {
+                            e.prevent_default();
+                            config.write().scores.set_status(&key_student_id, question_id, None);
+                        },
                         Code::Enter | Code::NumpadEnter | Code::ArrowDown | Code::NumpadAdd => {
                             e.prevent_default();
                             move_to_next.call(());
                         },
-                        Code::ArrowUp | Code::NumpadSubtract => {
+                        Code::ArrowUp => {
                             e.prevent_default();
                             move_to_prev.call(());
                         },
+                        Code::NumpadSubtract if !allows_negative => {
+                            e.prevent_default();
+                            move_to_prev.call(());
+                        },
+                        // the decimal point of "0.5"; keep it from the search hotkey
+                        Code::NumpadDecimal => e.stop_propagation(),
                         _ => {}
                     }
                 },
@@ -95,8 +158,145 @@ pub fn ScoreRow(
                     cur_question_id.set(Some(question_id)); 
                 }
             }
-            div { class: "text-sm opacity-60", " / {full}" }
-            p { class: "validator-hint", "Must be 0 to {full}" }
+            div { class: "text-sm opacity-60 flex items-center gap-1",
+                " / {full}"
+                if over_full {
+                    span { class: "text-warning text-xs", title: "満点を超えています", "超過" }
+                }
+                if let Some(st) = status {
+                    span { class: "badge badge-sm badge-warning", title: "{st.label()}", "{st.code()}" }
+                }
+                button {
+                    class: if has_comments { "btn btn-xs btn-info btn-outline" } else { "btn btn-xs btn-ghost" },
+                    title: "メモ・フィードバック",
+                    tabindex: "-1",
+                    onclick: move |_| comments_open.toggle(),
+                    "✎"
+                }
+            }
+            p { class: "validator-hint", "Must be {min} or more in steps of {step}" }
+            if let Some(question) = rubric {
+                RubricChecklist { question, student_id: key_student_id.clone(), ticked, config }
+            }
+            if !used_snippets.is_empty() {
+                div { class: "col-span-full flex flex-wrap gap-1 pl-4",
+                    for (sid, text) in used_snippets.into_iter() {
+                        {
+                            let student_id = student_id.clone();
+                            rsx! {
+                                span { class: "badge badge-sm badge-outline gap-1",
+                                    "{text}"
+                                    button {
+                                        class: "opacity-60 hover:opacity-100",
+                                        tabindex: "-1",
+                                        title: "取り消す",
+                                        onclick: move |_| {
+                                            config.write().scores.unuse_snippet(&student_id, question_id, sid);
+                                        },
+                                        "×"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if comments_open() {
+                div { class: "col-span-full grid grid-cols-1 md:grid-cols-2 gap-2 pb-1",
+                    textarea {
+                        class: "textarea textarea-bordered textarea-sm",
+                        placeholder: "採点者メモ（非公開）",
+                        // keep the grading hotkeys (F / J / L) out of the text
+                        onkeydown: move |e| keep_out_of_hotkeys(&e),
+                        value: "{note}",
+                        oninput: move |e| config.write().scores.set_note(&note_student_id, question_id, e.value()),
+                    }
+                    textarea {
+                        class: "textarea textarea-bordered textarea-sm",
+                        placeholder: "受験者へのフィードバック（個別レポートに出力）",
+                        onkeydown: move |e| keep_out_of_hotkeys(&e),
+                        value: "{feedback}",
+                        oninput: move |e| config.write().scores.set_feedback(&feedback_student_id, question_id, e.value()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RubricChecklist(
+    question: Question,
+    student_id: String,
+    // None = not scored by rubric yet
+    ticked: Option<Vec<u32>>,
+    config: Signal<Config>,
+) -> Element {
+
+    let checked = ticked.clone().unwrap_or_default();
+
+    rsx! {
+        div { class: "col-span-full flex flex-col gap-1 pl-4 pb-1",
+            for item in question.rubric.iter().cloned() {
+                {
+                    let on = checked.contains(&item.id);
+                    let question = question.clone();
+                    let student_id = student_id.clone();
+                    let mut next = checked.clone();
+                    if on {
+                        next.retain(|id| *id != item.id);
+                    } else {
+                        next.push(item.id);
+                    }
+                    let points = if item.deduction {
+                        format!("-{}", format_score(item.points))
+                    } else {
+                        format!("+{}", format_score(item.points))
+                    };
+                    rsx! {
+                        label { class: "flex items-center gap-2 text-sm cursor-pointer",
+                            input {
+                                r#type: "checkbox",
+                                class: "checkbox checkbox-xs",
+                                tabindex: "-1",
+                                checked: on,
+                                onchange: move |_| {
+                                    config.write().scores.set_ticked(&student_id, &question, next.clone());
+                                }
+                            }
+                            span { class: "flex-1", "{item.criterion}" }
+                            span { class: if item.deduction { "text-error" } else { "opacity-60" }, "{points}" }
+                        }
+                    }
+                }
+            }
+            if ticked.is_none() {
+                button {
+                    class: "btn btn-xs btn-ghost self-start",
+                    tabindex: "-1",
+                    onclick: move |_| {
+                        config.write().scores.set_ticked(&student_id, &question, Vec::new());
+                    },
+                    "該当なしで確定"
+                }
+            }
         }
     }
 }
+
+// Alt+1..9 -> snippet position
+fn snippet_number(code: Code) -> Option<usize> {
+    let n = match code {
+        Code::Digit1 | Code::Numpad1 => 0,
+        Code::Digit2 | Code::Numpad2 => 1,
+        Code::Digit3 | Code::Numpad3 => 2,
+        Code::Digit4 | Code::Numpad4 => 3,
+        Code::Digit5 | Code::Numpad5 => 4,
+        Code::Digit6 | Code::Numpad6 => 5,
+        Code::Digit7 | Code::Numpad7 => 6,
+        Code::Digit8 | Code::Numpad8 => 7,
+        Code::Digit9 | Code::Numpad9 => 8,
+        _ => return None,
+    };
+    Some(n)
+}
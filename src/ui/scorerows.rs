@@ -1,6 +1,7 @@
 use dioxus::prelude::*;
-use crate::models::Config;
-use crate::ui::ScoreRow;
+use crate::grading::{self, Hierarchy};
+use crate::models::{ChoiceRule, Config};
+use crate::ui::{AdjustmentPanel, ScoreRow};
 
 const TWO_COL_THRESHOLD: usize = 12;
 
@@ -12,15 +13,33 @@ pub fn ScoreRows(
     focus_idx: Signal<usize>,
     search_open: Signal<bool>,
 ) -> Element {
-    let questions = config.read().questions.clone();
+    let (questions, derived, dropped, fulls, student_id) = {
+        let cfg = config.read();
+        let student_id = cfg.students.get(cur_student_idx()).map(|s| s.id.clone());
+        let (derived, dropped, fulls) = student_id.as_deref()
+            .and_then(|sid| grading::student_result(&cfg, sid))
+            .map(|r| (r.scores, r.dropped, r.fulls))
+            .unwrap_or_default();
+        (cfg.questions.clone(), derived, dropped, fulls, student_id)
+    };
+    let tree = Hierarchy::new(&questions);
     let qlen = questions.len();
     let two_col = qlen >= TWO_COL_THRESHOLD;
-    let mid = (qlen + 1) / 2;
+    let mid = qlen.div_ceil(2);
+
+    // inputs exist for parts / plain questions only; focus moves over them
+    let leaves = tree.leaves();
+    let last = leaves.len().saturating_sub(1);
+    let order: Vec<usize> = if two_col {
+        (0..qlen).map(|i| if i % 2 == 0 { i / 2 } else { i / 2 + mid }).collect()
+    } else {
+        (0..qlen).collect()
+    };
 
     rsx! {
         div { class: "card bg-base-100 shadow",
             div { class: "card-body",
-                // global hotkeys: F opens search
+                // global hotkeys: F opens search (numpad . too, outside the score inputs)
                 onkeydown: move |e| {
                     match e.code() {
                         Code::KeyF | Code::NumpadDecimal => {
@@ -33,7 +52,7 @@ pub fn ScoreRows(
                         }
                         Code::KeyL | Code::NumpadMultiply => {
                             e.prevent_default();
-                            mv_next_student(cur_student_idx, config().students.len());
+                            mv_next_student(cur_student_idx, config.read().students.len());
                         }
                         Code::Escape => {
                             e.prevent_default();
@@ -57,7 +76,7 @@ pub fn ScoreRows(
                     button {
                         class: "btn max-w-xs",
                         onclick: move |_| {
-                            mv_next_student(cur_student_idx, config().students.len())
+                            mv_next_student(cur_student_idx, config.read().students.len())
                         },
                         "→"
                     }
@@ -69,65 +88,87 @@ pub fn ScoreRows(
                     div { class: "alert", "問題が未登録です" }
                 } else {
                     div { class: if two_col { "grid grid-cols-2 gap-x-4 gap-y-1" } else { "space-y-1" },
-                        if two_col {
-                            for i in 0..qlen {
-                                {
-                                    let row = i / 2;
-                                    let col = i % 2;
-                                    let original_idx = if col == 0 { row } else { row + mid };
-                                    let question = &questions[original_idx];
-                                        rsx! {
+                        for i in order.into_iter() {
+                            {
+                                let question = &questions[i];
+                                let depth = tree.depth(i);
+                                let is_dropped = dropped.get(i).copied().unwrap_or(false);
+                                if tree.is_group(i) {
+                                    // picked parts of this student; the static full score without a student
+                                    let full = fulls.get(i).copied()
+                                        .map(grading::format_score)
+                                        .unwrap_or_else(|| tree.full_score(&questions, i).to_string());
+                                    let total = derived.get(i).copied().flatten()
+                                        .map(grading::format_score)
+                                        .unwrap_or_else(|| "-".to_string());
+                                    let rule = question.choice.map(|c| match c.rule {
+                                        ChoiceRule::Best => format!("上位 {} 問", c.count),
+                                        ChoiceRule::Chosen => format!("{} 問選択", c.count),
+                                    });
+                                    rsx! {
+                                        div {
+                                            key: "group-{i}",
+                                            class: if is_dropped {
+                                                "flex items-baseline gap-2 border-b border-base-300 mt-2 opacity-40"
+                                            } else {
+                                                "flex items-baseline gap-2 border-b border-base-300 mt-2"
+                                            },
+                                            style: "padding-left: {depth}rem;",
+                                            span { class: "font-bold", "{question.name}" }
+                                            span { class: "text-sm opacity-60", "{total} / {full}" }
+                                            if let Some(rule) = rule {
+                                                span { class: "badge badge-sm badge-ghost", "{rule}" }
+                                            }
+                                        }
+                                    }
+                                } else {
+                                    let pos = leaves.iter().position(|&l| l == i).unwrap_or(0);
+                                    rsx! {
                                         ScoreRow {
-                                            key: "row-{original_idx}",
+                                            key: "row-{i}",
                                             question_id: question.id,
                                             cur_question_id,
                                             cur_student_idx,
-                                            qidx: original_idx,
+                                            qidx: pos,
+                                            depth,
+                                            dropped: is_dropped,
                                             config,
-                                            is_focused: focus_idx() == original_idx,
+                                            is_focused: focus_idx() == pos,
                                             move_to_next: move |_| {
-                                                let last = qlen.saturating_sub(1);
-                                                focus_idx.set(std::cmp::min(original_idx + 1, last));
+                                                focus_idx.set(std::cmp::min(pos + 1, last));
                                             },
                                             move_to_prev: move |_| {
-                                                focus_idx.set(original_idx.saturating_sub(1));
+                                                focus_idx.set(pos.saturating_sub(1));
                                             }
                                         }
                                     }
                                 }
                             }
-                        } else {
-                            for (qidx, question) in questions.iter().enumerate() {
-                                ScoreRow {
-                                    key: "row-{qidx}",
-                                    question_id: question.id,
-                                    cur_question_id,
-                                    cur_student_idx,
-                                    qidx,
-                                    config,
-                                    is_focused: focus_idx() == qidx,
-                                    move_to_next: move |_| {
-                                        let last = qlen.saturating_sub(1);
-                                        focus_idx.set(std::cmp::min(qidx + 1, last));
-                                    },
-                                    move_to_prev: move |_| {
-                                        focus_idx.set(qidx.saturating_sub(1));
-                                    }
-                                }
-                            }
                         }
                     }
+                    if let Some(student_id) = student_id {
+                        AdjustmentPanel { key: "{student_id}", config, student_id }
+                    }
                 }
             }
         }
     }
 }
 
+// For text fields inside the card: plain keys type text instead of reaching the
+// hotkeys above, while Ctrl / Meta chords (undo, redo) still reach App
+pub fn keep_out_of_hotkeys(e: &KeyboardEvent) {
+    let mods = e.modifiers();
+    if !(mods.contains(Modifiers::CONTROL) || mods.contains(Modifiers::META)) {
+        e.stop_propagation();
+    }
+}
+
 fn cur_student_label(
     config: Signal<Config>,
     cur_student_idx: Signal<usize>,
 ) -> String {
-    config().students
+    config.read().students
         .get(cur_student_idx())
         .map(|s| format!("{} {}", s.id, s.name))
         .unwrap_or_else(|| "No student".to_string())
@@ -0,0 +1,69 @@
+use dioxus::prelude::*;
+use crate::models::{CapPolicy, Config, Rounding, RoundingMode};
+
+// 集計設定
+#[component]
+pub fn ScoringSettingsCard(config: Signal<Config>) -> Element {
+
+    let cap_policy = config.read().cap_policy;
+    let rounding = config.read().rounding;
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body gap-3",
+
+                div { class: "card-title", "集計設定" }
+
+                label { class: "flex items-center gap-2",
+                    span { class: "text-sm w-32", "100点超過" }
+                    select {
+                        class: "select select-sm select-bordered",
+                        onchange: move |e| {
+                            config.write().cap_policy = match e.value().as_str() {
+                                "uncapped" => CapPolicy::Uncapped,
+                                _ => CapPolicy::Cap100,
+                            };
+                        },
+                        option { value: "cap100", selected: cap_policy == CapPolicy::Cap100, "100 で打ち切る" }
+                        option { value: "uncapped", selected: cap_policy == CapPolicy::Uncapped, "100 を超えてよい" }
+                    }
+                }
+
+                label { class: "flex items-center gap-2",
+                    span { class: "text-sm w-32", "端数処理" }
+                    select {
+                        class: "select select-sm select-bordered",
+                        onchange: move |e| {
+                            config.write().rounding.mode = match e.value().as_str() {
+                                "half_even" => RoundingMode::HalfEven,
+                                "floor" => RoundingMode::Floor,
+                                "ceil" => RoundingMode::Ceil,
+                                _ => RoundingMode::HalfUp,
+                            };
+                        },
+                        option { value: "half_up", selected: rounding.mode == RoundingMode::HalfUp, "四捨五入" }
+                        option { value: "half_even", selected: rounding.mode == RoundingMode::HalfEven, "偶数丸め" }
+                        option { value: "floor", selected: rounding.mode == RoundingMode::Floor, "切り捨て" }
+                        option { value: "ceil", selected: rounding.mode == RoundingMode::Ceil, "切り上げ" }
+                    }
+                }
+
+                label { class: "flex items-center gap-2",
+                    span { class: "text-sm w-32", "小数桁数" }
+                    select {
+                        class: "select select-sm select-bordered",
+                        onchange: move |e| {
+                            if let Ok(d) = e.value().parse::<u8>() {
+                                config.write().rounding.digits = d.min(Rounding::MAX_DIGITS);
+                            }
+                        },
+                        for d in 0..=Rounding::MAX_DIGITS {
+                            option { value: "{d}", selected: rounding.digits == d, "{d}" }
+                        }
+                    }
+                    span { class: "text-sm opacity-60", "例: 59.65 → {rounding.format(59.65)}" }
+                }
+            }
+        }
+    }
+}
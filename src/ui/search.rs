@@ -10,7 +10,8 @@ pub fn SearchWindow(
     // focus_idx: Signal<usize>,
 ) -> Element {
     let mut search_q = use_signal(String::new);
-    let mut search_results = use_signal(|| Vec::<Student>::new());
+    // hits with the matching comment, if the hit came from a note / feedback
+    let mut search_results = use_signal(Vec::<(Student, Option<String>)>::new);
 
     // 追加: 検索結果内の選択位置
     let mut selected_idx = use_signal(|| 0usize);
@@ -28,18 +29,43 @@ pub fn SearchWindow(
 
             let q_upper = q_trim.to_uppercase();
 
-            let all = config().students.clone();
-            let mut hits: Vec<Student> = all
-                .into_iter()
+            let cfg = config.read();
+            let mut hits: Vec<(Student, Option<String>)> = cfg.students
+                .iter()
                 .filter(|s| {
                     let id_hit = s.id.to_uppercase().contains(&q_upper);
                     let name_hit = s.name.contains(&q_trim);
                     id_hit || name_hit
                 })
                 .take(30)
+                .map(|s| (s.clone(), None))
                 .collect();
 
-            hits.sort_by(|a, b| a.id.cmp(&b.id));
+            // メモ・フィードバックの本文
+            for sc in cfg.scores.iter() {
+                if hits.len() >= 30 {
+                    break;
+                }
+                if hits.iter().any(|(s, _)| s.id == sc.student_id) {
+                    continue;
+                }
+                let Some(text) = [&sc.feedback, &sc.note].into_iter()
+                    .find(|t| t.to_uppercase().contains(&q_upper)) else {
+                    continue;
+                };
+                let Some(student) = cfg.students.iter().find(|s| s.id == sc.student_id) else {
+                    continue;
+                };
+                let q_name = cfg.questions.iter()
+                    .find(|q| q.id == sc.question_id)
+                    .map(|q| q.name.as_str())
+                    .unwrap_or("?");
+                let line = text.lines().find(|l| l.to_uppercase().contains(&q_upper)).unwrap_or(text);
+                hits.push((student.clone(), Some(format!("{q_name}: {line}"))));
+            }
+            drop(cfg);
+
+            hits.sort_by(|a, b| a.0.id.cmp(&b.0.id));
 
             search_results.set(hits);
             selected_idx.set(0); // 新しい検索ごとに先頭を選択
@@ -53,7 +79,7 @@ pub fn SearchWindow(
         let mut cur_student_idx = cur_student_idx;
         // let mut focus_idx = focus_idx;
         move |sid: String| {
-            if let Some(pos) = config().students.iter().position(|x| x.id == sid) {
+            if let Some(pos) = config.read().students.iter().position(|x| x.id == sid) {
                 cur_student_idx.set(pos);
             } else {
                 msg.set("選択した学生が見つかりません（一覧が更新された可能性）".to_string());
@@ -67,7 +93,7 @@ pub fn SearchWindow(
         div { class: "modal modal-open",
             div { class: "modal-box w-11/12 max-w-3xl",
                 div { class: "flex items-center gap-3",
-                    h3 { class: "font-bold text-lg", "検索（学籍番号/氏名）" }
+                    h3 { class: "font-bold text-lg", "検索（学籍番号/氏名/コメント）" }
                     div { class: "ml-auto",
                         button {
                             class: "btn btn-sm",
@@ -80,7 +106,7 @@ pub fn SearchWindow(
                 input {
                     id: "search",
                     class: "input input-bordered w-full mt-3",
-                    placeholder: "例: A001 / 山田",
+                    placeholder: "例: A001 / 山田 / 単位",
                     value: "{search_q()}",
                     autofocus: true,
 
@@ -114,7 +140,7 @@ pub fn SearchWindow(
                                 let n = search_results.read().len();
                                 if n > 0 {
                                     let i = selected_idx().min(n - 1);
-                                    if let Some(s) = search_results.read().get(i) {
+                                    if let Some((s, _)) = search_results.read().get(i) {
                                         select_student(s.id.clone());
                                     }
                                 }
@@ -125,7 +151,7 @@ pub fn SearchWindow(
                 }
 
                 div { class: "mt-3 max-h-72 overflow-auto border border-base-300 rounded",
-                    for (i, s) in search_results().iter().enumerate() {
+                    for (i, (s, comment)) in search_results().iter().enumerate() {
                         {
                             let sid = s.id.clone();
                             let cls = if i == selected_idx() {
@@ -141,6 +167,9 @@ pub fn SearchWindow(
                                     },
                                     span { class: "font-mono", "{s.id}" }
                                     span { class: "ml-3", "{s.name}" }
+                                    if let Some(comment) = comment {
+                                        div { class: "text-sm opacity-60 truncate", "{comment}" }
+                                    }
                                 }
                             }
                         }
@@ -0,0 +1,101 @@
+use dioxus::prelude::*;
+use crate::models::{Config, Snippet};
+
+// Feedback snippets of one question, with how often each was used
+#[component]
+pub fn SnippetBank(
+    config: Signal<Config>,
+    question_id: u32,
+) -> Element {
+
+    let (snippets, usage) = {
+        let cfg = config.read();
+        let snippets = cfg.questions.iter()
+            .find(|q| q.id == question_id)
+            .map(|q| q.snippets.clone())
+            .unwrap_or_default();
+        (snippets, cfg.scores.snippet_usage(question_id))
+    };
+    let max_usage = usage.values().copied().max().unwrap_or(0).max(1);
+
+    let mut edit = move |i: usize, f: &dyn Fn(&mut Snippet)| {
+        let mut cfg = config.write();
+        if let Some(sn) = cfg.questions.iter_mut()
+            .find(|q| q.id == question_id)
+            .and_then(|q| q.snippets.get_mut(i))
+        {
+            f(sn);
+        }
+    };
+
+    rsx! {
+        div { class: "flex flex-col gap-1",
+            div { class: "flex items-center gap-2",
+                span { class: "font-semibold text-sm", "定型コメント" }
+                span { class: "text-xs opacity-60", "点数欄で Alt+番号 で挿入" }
+                div { class: "flex-1" }
+                button {
+                    class: "btn btn-xs",
+                    onclick: move |_| {
+                        let mut cfg = config.write();
+                        if let Some(q) = cfg.questions.iter_mut().find(|q| q.id == question_id) {
+                            let next_id = q.snippets.iter().map(|sn| sn.id).max().unwrap_or(0).saturating_add(1);
+                            q.snippets.push(Snippet { id: next_id, text: String::new(), deduction: 0.0 });
+                        }
+                    },
+                    "追加"
+                }
+            }
+            for (i, sn) in snippets.into_iter().enumerate() {
+                {
+                    let count = usage.get(&sn.id).copied().unwrap_or(0);
+                    let bar = count as f32 / max_usage as f32 * 100.0;
+                    rsx! {
+                        div { key: "{sn.id}", class: "flex items-center gap-2",
+                            kbd { class: if i < 9 { "kbd kbd-sm" } else { "kbd kbd-sm opacity-30" }, "{i + 1}" }
+                            input {
+                                class: "input input-bordered input-sm flex-1",
+                                placeholder: "例: 単位が抜けています",
+                                value: "{sn.text}",
+                                oninput: move |e| {
+                                    let v = e.value();
+                                    edit(i, &|sn| sn.text = v.clone());
+                                }
+                            }
+                            input {
+                                class: "input input-bordered input-sm w-20",
+                                r#type: "number",
+                                step: "0.5",
+                                min: "0",
+                                title: "減点",
+                                value: "{sn.deduction}",
+                                oninput: move |e| {
+                                    let v = e.value().parse::<f32>().unwrap_or(0.0).max(0.0);
+                                    edit(i, &|sn| sn.deduction = v);
+                                }
+                            }
+                            div { class: "w-16 flex flex-col", title: "使用回数",
+                                span { class: "text-xs text-right", "{count}" }
+                                progress { class: "progress progress-warning h-1", value: "{bar}", max: "100" }
+                            }
+                            button {
+                                class: "btn btn-xs btn-ghost",
+                                onclick: move |_| {
+                                    let mut cfg = config.write();
+                                    let Some(q) = cfg.questions.iter_mut().find(|q| q.id == question_id) else {
+                                        return;
+                                    };
+                                    if i < q.snippets.len() {
+                                        let id = q.snippets.remove(i).id;
+                                        cfg.scores.purge_snippet(question_id, id);
+                                    }
+                                },
+                                "Del"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use dioxus::prelude::*;
+use crate::models::{Config, ScoreStatus, StatusScoring};
+
+// 欠席 / 免除 / 未提出 の扱い
+#[component]
+pub fn StatusPolicyCard(config: Signal<Config>) -> Element {
+
+    let policy = config.read().status_policy.clone();
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body gap-3",
+
+                div { class: "card-title", "欠席・免除・未提出の扱い" }
+
+                table { class: "table table-sm",
+                    thead {
+                        tr {
+                            th { "区分" }
+                            th { "キー" }
+                            th { "合計点" }
+                            th { "入力済みに数える" }
+                            th { "統計に含める" }
+                        }
+                    }
+                    tbody {
+                        for status in ScoreStatus::ALL {
+                            {
+                                let rule = *policy.rule(status);
+                                let key = match status {
+                                    ScoreStatus::Absent => "A",
+                                    ScoreStatus::Exempt => "E",
+                                    ScoreStatus::NotSubmitted => "N",
+                                };
+                                rsx! {
+                                    tr {
+                                        td { "{status.label()} ({status.code()})" }
+                                        td { kbd { class: "kbd kbd-sm", "{key}" } }
+                                        td {
+                                            select {
+                                                class: "select select-sm select-bordered",
+                                                onchange: move |e| {
+                                                    let scoring = match e.value().as_str() {
+                                                        "exclude" => StatusScoring::Exclude,
+                                                        "blank" => StatusScoring::Blank,
+                                                        _ => StatusScoring::Zero,
+                                                    };
+                                                    config.write().status_policy.rule_mut(status).scoring = scoring;
+                                                },
+                                                option { value: "zero", selected: rule.scoring == StatusScoring::Zero, "0点" }
+                                                option { value: "exclude", selected: rule.scoring == StatusScoring::Exclude, "除外して再配分" }
+                                                option { value: "blank", selected: rule.scoring == StatusScoring::Blank, "未入力扱い" }
+                                            }
+                                        }
+                                        td {
+                                            input {
+                                                r#type: "checkbox",
+                                                class: "checkbox checkbox-sm",
+                                                checked: rule.complete,
+                                                onchange: move |e| {
+                                                    config.write().status_policy.rule_mut(status).complete = e.checked();
+                                                }
+                                            }
+                                        }
+                                        td {
+                                            input {
+                                                r#type: "checkbox",
+                                                class: "checkbox checkbox-sm",
+                                                checked: rule.in_stats,
+                                                onchange: move |e| {
+                                                    config.write().status_policy.rule_mut(status).in_stats = e.checked();
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,6 +2,8 @@ use dioxus::prelude::*;
 
 use crate::Page;
 use crate::models::Config;
+use crate::ui::{BackupDialog, SaveAction, ValidationDialog};
+use crate::validation;
 
 #[component]
 pub fn TopBar(
@@ -10,6 +12,23 @@ pub fn TopBar(
 ) -> Element {
 
     let msg = use_signal(String::new);
+    let mut backups_open = use_signal(|| false);
+    let mut check_open = use_signal(|| false);
+    let mut pending_save = use_signal(|| None::<SaveAction>);
+
+    // check integrity before saving; saving anyway is left to the dialog.
+    // Warnings alone do not stop the save.
+    let mut save = move |action: SaveAction| {
+        if validation::validate(&config.read()).iter().all(|i| i.is_warning()) {
+            match action {
+                SaveAction::Save => Config::save(config, msg),
+                SaveAction::SaveAs => Config::save_as(config, msg),
+            }
+        } else {
+            pending_save.set(Some(action));
+            check_open.set(true);
+        }
+    };
 
     rsx! {
         div { class: "navbar bg-base-100 rounded-box shadow mb-2",
@@ -18,6 +37,7 @@ pub fn TopBar(
                 button { class: "btn btn-sm", onclick: move |_| on_nav.call(Page::MasterQuestions), "問題設定" }
                 button { class: "btn btn-sm", onclick: move |_| on_nav.call(Page::MasterStudents), "受験者設定" }
                 button { class: "btn btn-sm", onclick: move |_| on_nav.call(Page::Rating), "成績評価" }
+                button { class: "btn btn-sm", onclick: move |_| on_nav.call(Page::Analysis), "問題分析" }
                 button { class: "btn btn-sm", onclick: move |_| on_nav.call(Page::Grading), "採点" }
             }
 
@@ -30,11 +50,26 @@ pub fn TopBar(
             }
 
             div { class: "navbar-end gap-2",
-                button { class: "btn btn-sm btn-primary", onclick: move |_| Config::save(config, msg), "Save" }
-                button { class: "btn btn-sm", onclick: move |_| Config::save_as(config, msg), "Save as" }
-                button { class: "btn btn-sm", onclick: move |_| Config::load(config, msg), "Load" }
+                button { class: "btn btn-sm btn-primary", onclick: move |_| save(SaveAction::Save), "Save" }
+                button { class: "btn btn-sm", onclick: move |_| save(SaveAction::SaveAs), "Save as" }
+                button { class: "btn btn-sm", onclick: move |_| Config::load(config, msg, check_open), "Load" }
+                button { class: "btn btn-sm btn-ghost", onclick: move |_| check_open.set(true), "Check" }
+                button {
+                    class: "btn btn-sm btn-ghost",
+                    disabled: config.read().save_path.is_none(),
+                    onclick: move |_| backups_open.set(true),
+                    "Backups"
+                }
             }
         }
+
+        { backups_open().then(|| rsx! {
+            BackupDialog { is_open: backups_open, config, msg }
+        })}
+
+        { check_open().then(|| rsx! {
+            ValidationDialog { is_open: check_open, config, msg, pending_save }
+        })}
     }
 }
 
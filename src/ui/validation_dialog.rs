@@ -0,0 +1,108 @@
+use dioxus::prelude::*;
+use crate::models::Config;
+use crate::validation::{self, Fix};
+
+// Save requested while the config had issues
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveAction {
+    Save,
+    SaveAs,
+}
+
+#[component]
+pub fn ValidationDialog(
+    is_open: Signal<bool>,
+    config: Signal<Config>,
+    msg: Signal<String>,
+    pending_save: Signal<Option<SaveAction>>,
+) -> Element {
+
+    let (issues, fixes) = {
+        let cfg = config.read();
+        let issues = validation::validate(&cfg);
+        let fixes: Vec<Vec<Fix>> = issues.iter().map(|i| i.fixes(&cfg)).collect();
+        (issues, fixes)
+    };
+
+    let mut apply = move |fix: Fix| {
+        let n = fix.apply(&mut config.write());
+        msg.set(format!("{}: {n} 件", fix.label()));
+    };
+
+    let mut close = move || {
+        pending_save.set(None);
+        is_open.set(false);
+    };
+
+    rsx! {
+        div { class: "modal modal-open",
+            div { class: "modal-box w-11/12 max-w-3xl",
+                h3 { class: "font-bold text-lg", "データの整合性チェック" }
+
+                if issues.is_empty() {
+                    div { class: "alert alert-success mt-3", "問題はありません" }
+                } else {
+                    div { class: "mt-3 max-h-96 overflow-auto space-y-2",
+                        for (issue, fixes) in issues.into_iter().zip(fixes) {
+                            {
+                                let (renames, actions): (Vec<Fix>, Vec<Fix>) = fixes.into_iter().partition(|f| {
+                                    matches!(f, Fix::RenameStudentScores { .. } | Fix::RenameQuestionScores { .. })
+                                });
+                                rsx! {
+                                    div { class: "flex flex-wrap items-center gap-2 p-2 rounded bg-base-200",
+                                        if issue.is_warning() {
+                                            span { class: "badge badge-sm badge-warning", "警告" }
+                                        }
+                                        span { class: "text-sm flex-1", "{issue.message()}" }
+                                        for fix in actions.into_iter() {
+                                            button {
+                                                class: "btn btn-xs",
+                                                onclick: move |_| apply(fix.clone()),
+                                                "{fix.label()}"
+                                            }
+                                        }
+                                        if !renames.is_empty() {
+                                            select {
+                                                class: "select select-xs select-bordered",
+                                                onchange: move |e| {
+                                                    if let Some(fix) = e.value().parse::<usize>().ok().and_then(|i| renames.get(i)) {
+                                                        apply(fix.clone());
+                                                    }
+                                                },
+                                                option { value: "", selected: true, "点数の移動先..." }
+                                                for (i, fix) in renames.iter().enumerate() {
+                                                    option { value: "{i}", "{fix.label()}" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "modal-action",
+                    if let Some(action) = pending_save() {
+                        button {
+                            class: "btn btn-sm btn-primary",
+                            onclick: move |_| {
+                                close();
+                                match action {
+                                    SaveAction::Save => Config::save(config, msg),
+                                    SaveAction::SaveAs => Config::save_as(config, msg),
+                                }
+                            },
+                            "このまま保存"
+                        }
+                    }
+                    button {
+                        class: "btn btn-sm",
+                        onclick: move |_| close(),
+                        "閉じる"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,418 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::grading::{format_score, Hierarchy};
+use crate::models::{CapPolicy, Config, Question};
+
+// Referential integrity checks over Config.
+// Run after a file is loaded and before it is saved; each issue offers
+// the fixes that can be applied to it without guessing.
+// Scores above full_score are allowed as extra credit. They are only reported, as a warning
+// that does not hold up saving, when they can lift the total: a normal question with the
+// total not capped at 100.
+// Rubric-scored cells are not checked against min_score or step: their value is derived
+// from the ticked items, and a fix would have to drop the ticks kept for appeals.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    DuplicateStudentId { id: String, count: usize },
+    DuplicateQuestionId { id: u32, count: usize },
+    EmptyStudentId { row: usize },
+    // scores of a student id that is not in the roster
+    OrphanStudent { id: String, scores: usize },
+    // scores of a question id that is not in the question list
+    OrphanQuestion { id: u32, scores: usize },
+    // score below the question's min_score, e.g. after min_score was raised
+    BelowMin { student_id: String, question_id: u32, score: f32, min_score: f32 },
+    // score that is not a multiple of the question's step
+    OffStep { student_id: String, question_id: u32, score: f32, step: f32 },
+    // score above full_score of a normal question while the total is uncapped (warning only)
+    OverFull { student_id: String, question_id: u32, score: f32, full_score: u32 },
+    // parent_id that is missing or loops back; the question is treated as top-level
+    InvalidParent { id: u32, parent_id: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    // delete every score whose student or question does not exist
+    PurgeOrphans,
+    // raise every score below min_score to min_score
+    ClampToMin,
+    // round every off-step score to the nearest step
+    SnapToStep,
+    // move orphaned scores onto an existing id
+    RenameStudentScores { from: String, to: String },
+    RenameQuestionScores { from: u32, to: u32 },
+}
+
+impl Issue {
+
+    pub fn message(&self) -> String {
+        match self {
+            Issue::DuplicateStudentId { id, count } =>
+                format!("受験者 id \"{id}\" が {count} 件重複しています"),
+            Issue::DuplicateQuestionId { id, count } =>
+                format!("問題 id {id} が {count} 件重複しています"),
+            Issue::EmptyStudentId { row } =>
+                format!("受験者 {} 行目の id が空です", row + 1),
+            Issue::OrphanStudent { id, scores } =>
+                format!("存在しない受験者 id \"{id}\" の点数が {scores} 件あります"),
+            Issue::OrphanQuestion { id, scores } =>
+                format!("存在しない問題 id {id} の点数が {scores} 件あります"),
+            Issue::BelowMin { student_id, question_id, score, min_score } =>
+                format!("{student_id} / 問題 {question_id}: {} 点が最低点 {} を下回っています", format_score(*score), format_score(*min_score)),
+            Issue::OffStep { student_id, question_id, score, step } =>
+                format!("{student_id} / 問題 {question_id}: {} 点が刻み {} に合っていません", format_score(*score), format_score(*step)),
+            Issue::InvalidParent { id, parent_id } =>
+                format!("問題 {id} の親 {parent_id} が存在しないか循環しています"),
+            Issue::OverFull { student_id, question_id, score, full_score } =>
+                format!("{student_id} / 問題 {question_id}: {} 点が満点 {full_score} を超えています（合計は上限なし）", format_score(*score)),
+        }
+    }
+
+    // Warnings are shown but do not hold up saving
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Issue::OverFull { .. })
+    }
+
+    // Fixes that apply to this issue
+    pub fn fixes(&self, config: &Config) -> Vec<Fix> {
+        match self {
+            Issue::OrphanStudent { id, .. } => {
+                let mut fixes = vec![Fix::PurgeOrphans];
+                fixes.extend(unscored_students(config).into_iter().map(|to| {
+                    Fix::RenameStudentScores { from: id.clone(), to }
+                }));
+                fixes
+            }
+            Issue::OrphanQuestion { id, .. } => {
+                let mut fixes = vec![Fix::PurgeOrphans];
+                fixes.extend(unscored_questions(config).into_iter().map(|to| {
+                    Fix::RenameQuestionScores { from: *id, to }
+                }));
+                fixes
+            }
+            Issue::BelowMin { .. } => vec![Fix::ClampToMin],
+            Issue::OffStep { .. } => vec![Fix::SnapToStep],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Fix {
+
+    pub fn label(&self) -> String {
+        match self {
+            Fix::PurgeOrphans => "孤立した点数を削除".to_string(),
+            Fix::ClampToMin => "最低点に揃える".to_string(),
+            Fix::SnapToStep => "刻みに丸める".to_string(),
+            Fix::RenameStudentScores { to, .. } => format!("\"{to}\" の点数にする"),
+            Fix::RenameQuestionScores { to, .. } => format!("問題 {to} の点数にする"),
+        }
+    }
+
+    // Apply the fix; returns the number of changed scores
+    pub fn apply(&self, config: &mut Config) -> usize {
+        match self {
+            Fix::PurgeOrphans => {
+                let students: HashSet<String> = config.students.iter().map(|s| s.id.clone()).collect();
+                let questions: HashSet<u32> = config.questions.iter().map(|q| q.id).collect();
+                let before = config.scores.len();
+                config.scores.retain(|sc| {
+                    students.contains(&sc.student_id) && questions.contains(&sc.question_id)
+                });
+                before - config.scores.len()
+            }
+            Fix::ClampToMin => {
+                let low: Vec<(String, u32, f32)> = below_min(config)
+                    .map(|(sid, qid, _, q)| (sid.to_string(), qid, q.min_score))
+                    .collect();
+                for (sid, qid, score) in low.iter() {
+                    config.scores.set_value(sid, *qid, Some(*score));
+                }
+                low.len()
+            }
+            Fix::SnapToStep => {
+                let off: Vec<(String, u32, f32)> = off_step(config)
+                    .map(|(sid, qid, score, q)| (sid.to_string(), qid, q.snap(score)))
+                    .collect();
+                for (sid, qid, score) in off.iter() {
+                    config.scores.set_value(sid, *qid, Some(*score));
+                }
+                off.len()
+            }
+            Fix::RenameStudentScores { from, to } => config.scores.rename_student(from, to),
+            Fix::RenameQuestionScores { from, to } => config.scores.rename_question(*from, *to),
+        }
+    }
+}
+
+pub fn validate(config: &Config) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    for (id, count) in duplicates(config.students.iter().map(|s| s.id.clone())) {
+        issues.push(Issue::DuplicateStudentId { id, count });
+    }
+    for (id, count) in duplicates(config.questions.iter().map(|q| q.id)) {
+        issues.push(Issue::DuplicateQuestionId { id, count });
+    }
+    for (row, s) in config.students.iter().enumerate() {
+        if s.id.trim().is_empty() {
+            issues.push(Issue::EmptyStudentId { row });
+        }
+    }
+
+    let students: HashSet<&str> = config.students.iter().map(|s| s.id.as_str()).collect();
+    let questions: HashSet<u32> = config.questions.iter().map(|q| q.id).collect();
+    let mut orphan_students: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut orphan_questions: BTreeMap<u32, usize> = BTreeMap::new();
+    for sc in config.scores.iter() {
+        if !students.contains(sc.student_id.as_str()) {
+            *orphan_students.entry(sc.student_id.as_str()).or_default() += 1;
+        } else if !questions.contains(&sc.question_id) {
+            *orphan_questions.entry(sc.question_id).or_default() += 1;
+        }
+    }
+    issues.extend(orphan_students.into_iter().map(|(id, scores)| {
+        Issue::OrphanStudent { id: id.to_string(), scores }
+    }));
+    issues.extend(orphan_questions.into_iter().map(|(id, scores)| {
+        Issue::OrphanQuestion { id, scores }
+    }));
+
+    issues.extend(below_min(config).map(|(sid, qid, score, q)| {
+        Issue::BelowMin { student_id: sid.to_string(), question_id: qid, score, min_score: q.min_score }
+    }));
+    issues.extend(off_step(config).map(|(sid, qid, score, q)| {
+        Issue::OffStep { student_id: sid.to_string(), question_id: qid, score, step: q.step }
+    }));
+    issues.extend(over_full(config).map(|(sid, qid, score, q)| {
+        Issue::OverFull { student_id: sid.to_string(), question_id: qid, score, full_score: q.full_score }
+    }));
+
+    let hierarchy = Hierarchy::new(&config.questions);
+    for (i, q) in config.questions.iter().enumerate() {
+        if let (Some(parent_id), None) = (q.parent_id, hierarchy.parent(i)) {
+            issues.push(Issue::InvalidParent { id: q.id, parent_id });
+        }
+    }
+
+    issues
+}
+
+// Ids that occur more than once, with their counts, in first-seen order
+pub fn duplicates<T: Clone + Eq + std::hash::Hash>(ids: impl Iterator<Item = T>) -> Vec<(T, usize)> {
+    let mut order = Vec::new();
+    let mut counts: HashMap<T, usize> = HashMap::new();
+    for id in ids {
+        let c = counts.entry(id.clone()).or_default();
+        if *c == 0 {
+            order.push(id);
+        }
+        *c += 1;
+    }
+    order.into_iter()
+        .filter_map(|id| {
+            let c = counts[&id];
+            (c > 1).then_some((id, c))
+        })
+        .collect()
+}
+
+fn below_min(config: &Config) -> impl Iterator<Item = (&str, u32, f32, &Question)> {
+    let questions: HashMap<u32, &Question> = config.questions.iter().map(|q| (q.id, q)).collect();
+    config.scores.iter().filter(|sc| sc.ticked.is_none()).filter_map(move |sc| {
+        let score = sc.score?;
+        let q = *questions.get(&sc.question_id)?;
+        (score < q.min_score).then_some((sc.student_id.as_str(), sc.question_id, score, q))
+    })
+}
+
+fn off_step(config: &Config) -> impl Iterator<Item = (&str, u32, f32, &Question)> {
+    let questions: HashMap<u32, &Question> = config.questions.iter().map(|q| (q.id, q)).collect();
+    config.scores.iter().filter(|sc| sc.ticked.is_none()).filter_map(move |sc| {
+        let score = sc.score?;
+        let q = *questions.get(&sc.question_id)?;
+        (!q.on_step(score)).then_some((sc.student_id.as_str(), sc.question_id, score, q))
+    })
+}
+
+// Bonus questions and a total capped at 100 absorb extra credit, so only the rest count
+fn over_full(config: &Config) -> impl Iterator<Item = (&str, u32, f32, &Question)> {
+    let uncapped = config.cap_policy == CapPolicy::Uncapped;
+    let questions: HashMap<u32, &Question> = config.questions.iter()
+        .filter(|q| uncapped && !q.bonus)
+        .map(|q| (q.id, q))
+        .collect();
+    config.scores.iter().filter_map(move |sc| {
+        let score = sc.score?;
+        let q = *questions.get(&sc.question_id)?;
+        q.over_full(score).then_some((sc.student_id.as_str(), sc.question_id, score, q))
+    })
+}
+
+// Roster ids without any score, candidates for orphaned scores
+fn unscored_students(config: &Config) -> Vec<String> {
+    let scored: HashSet<&str> = config.scores.iter().map(|sc| sc.student_id.as_str()).collect();
+    config.students.iter()
+        .filter(|s| !scored.contains(s.id.as_str()))
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+fn unscored_questions(config: &Config) -> Vec<u32> {
+    let scored: HashSet<u32> = config.scores.iter().map(|sc| sc.question_id).collect();
+    config.questions.iter()
+        .filter(|q| !scored.contains(&q.id))
+        .map(|q| q.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Student;
+
+    fn sample() -> Config {
+        let mut cfg = Config::new();
+        cfg.questions = vec![
+            Question { id: 1, name: "Q1".into(), full_score: 10, ..Default::default() },
+            Question { id: 1, name: "Q1'".into(), full_score: 10, ..Default::default() },
+        ];
+        cfg.students = vec![
+            Student { id: "S1".into(), name: String::new() },
+            Student { id: "S2".into(), name: String::new() },
+        ];
+        cfg.scores.set("S1", 1, Some(12.0));
+        cfg.scores.set("S9", 1, Some(3.0));
+        cfg.scores.set("S1", 7, Some(3.0));
+        cfg
+    }
+
+    #[test]
+    fn reports_every_kind_of_issue() {
+        let issues = validate(&sample());
+        assert_eq!(issues, vec![
+            Issue::DuplicateQuestionId { id: 1, count: 2 },
+            Issue::OrphanStudent { id: "S9".into(), scores: 1 },
+            Issue::OrphanQuestion { id: 7, scores: 1 },
+        ]);
+    }
+
+    #[test]
+    fn fixes_resolve_issues() {
+        let mut cfg = sample();
+        let orphan = Issue::OrphanStudent { id: "S9".into(), scores: 1 };
+        let fixes = orphan.fixes(&cfg);
+        assert_eq!(fixes[1], Fix::RenameStudentScores { from: "S9".into(), to: "S2".into() });
+        assert_eq!(fixes[1].apply(&mut cfg), 1);
+        assert_eq!(cfg.scores.value("S2", 1), Some(3.0));
+
+        assert_eq!(Fix::PurgeOrphans.apply(&mut cfg), 1);
+        assert_eq!(validate(&cfg), vec![Issue::DuplicateQuestionId { id: 1, count: 2 }]);
+        // the over-full score is kept
+        assert_eq!(cfg.scores.value("S1", 1), Some(12.0));
+    }
+
+    #[test]
+    fn over_full_is_a_warning_when_uncapped() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![
+            Question { id: 1, full_score: 10, ..Default::default() },
+            Question { id: 2, full_score: 10, bonus: true, ..Default::default() },
+        ];
+        cfg.students = vec![Student { id: "S1".into(), name: String::new() }];
+        cfg.scores.set("S1", 1, Some(12.0));
+        cfg.scores.set("S1", 2, Some(12.0));
+        assert!(validate(&cfg).is_empty());
+
+        cfg.cap_policy = CapPolicy::Uncapped;
+        let issues = validate(&cfg);
+        assert_eq!(issues, vec![
+            Issue::OverFull { student_id: "S1".into(), question_id: 1, score: 12.0, full_score: 10 },
+        ]);
+        assert!(issues[0].is_warning());
+        assert!(issues[0].fixes(&cfg).is_empty());
+    }
+
+    #[test]
+    fn off_step_scores_are_snapped() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![Question { id: 1, full_score: 10, step: 0.5, ..Default::default() }];
+        cfg.students = vec![Student { id: "S1".into(), name: String::new() }];
+        cfg.scores.set("S1", 1, Some(3.3));
+        assert_eq!(validate(&cfg), vec![
+            Issue::OffStep { student_id: "S1".into(), question_id: 1, score: 3.3, step: 0.5 },
+        ]);
+        assert_eq!(Fix::SnapToStep.apply(&mut cfg), 1);
+        assert_eq!(cfg.scores.value("S1", 1), Some(3.5));
+        assert!(validate(&cfg).is_empty());
+    }
+
+    #[test]
+    fn fixes_keep_ticks_and_snippets() {
+        use crate::models::{RubricItem, Snippet};
+        let mut cfg = Config::new();
+        cfg.questions = vec![
+            Question {
+                id: 1,
+                full_score: 10,
+                step: 2.0,
+                rubric: vec![RubricItem { id: 1, criterion: String::new(), points: 3.0, deduction: false }],
+                ..Default::default()
+            },
+            Question {
+                id: 2,
+                full_score: 10,
+                step: 2.0,
+                snippets: vec![Snippet { id: 1, text: "単位なし".into(), deduction: 1.0 }],
+                ..Default::default()
+            },
+        ];
+        cfg.students = vec![Student { id: "S1".into(), name: String::new() }];
+        // 3 points from the rubric are off the step of 2, but derived from the ticks
+        let (q1, q2) = (cfg.questions[0].clone(), cfg.questions[1].clone());
+        cfg.scores.set_ticked("S1", &q1, vec![1]);
+        cfg.scores.set("S1", 2, Some(6.0));
+        cfg.scores.use_snippet("S1", &q2, 1);
+        assert_eq!(validate(&cfg), vec![
+            Issue::OffStep { student_id: "S1".into(), question_id: 2, score: 5.0, step: 2.0 },
+        ]);
+
+        assert_eq!(Fix::SnapToStep.apply(&mut cfg), 1);
+        assert_eq!(cfg.scores.value("S1", 1), Some(3.0));
+        assert_eq!(cfg.scores.ticked("S1", 1), Some(&[1][..]));
+        let cell = cfg.scores.get("S1", 2).unwrap();
+        assert_eq!(cell.score, Some(6.0));
+        assert_eq!(cell.snippets.len(), 1);
+        assert_eq!(cell.feedback, "単位なし");
+    }
+
+    #[test]
+    fn scores_below_min_are_clamped() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![Question { id: 1, full_score: 10, min_score: -1.0, ..Default::default() }];
+        cfg.students = vec![Student { id: "S1".into(), name: String::new() }];
+        cfg.scores.set("S1", 1, Some(-3.0));
+        assert_eq!(validate(&cfg), vec![
+            Issue::BelowMin { student_id: "S1".into(), question_id: 1, score: -3.0, min_score: -1.0 },
+        ]);
+        assert_eq!(Fix::ClampToMin.apply(&mut cfg), 1);
+        assert_eq!(cfg.scores.value("S1", 1), Some(-1.0));
+        assert!(validate(&cfg).is_empty());
+    }
+
+    #[test]
+    fn dangling_parent_is_reported() {
+        let mut cfg = Config::new();
+        cfg.questions = vec![
+            Question { id: 1, parent_id: Some(9), ..Default::default() },
+            Question { id: 2, parent_id: Some(3), ..Default::default() },
+            Question { id: 3, parent_id: Some(2), ..Default::default() },
+            Question { id: 4, parent_id: Some(3), ..Default::default() },
+        ];
+        assert_eq!(validate(&cfg), vec![
+            Issue::InvalidParent { id: 1, parent_id: 9 },
+            Issue::InvalidParent { id: 2, parent_id: 3 },
+            Issue::InvalidParent { id: 3, parent_id: 2 },
+        ]);
+    }
+}